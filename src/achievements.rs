@@ -0,0 +1,206 @@
+use crate::utils::log;
+use crate::utils::local_storage;
+use crate::world::{GameMode, World};
+use crate::profile;
+use crate::i18n;
+use std::collections::BTreeMap;
+
+use wasm_bindgen::prelude::*;
+
+const STORAGE_KEY: &str = "achievements";
+const TOAST_DURATION_MS: i32 = 3000;
+
+
+enum Condition {
+    // reach a speed in a game mode
+    Speed(GameMode, u32),
+    // eat food in a row without pausing
+    FoodWithoutPause(u32),
+    // survive seconds in a game mode
+    Survive(GameMode, u32),
+    // fill percent of the board with the snake
+    BoardFilled(u32),
+}
+
+impl Condition {
+    fn target(&self) -> u32 {
+        match self {
+            Condition::Speed(_, speed) => *speed,
+            Condition::FoodWithoutPause(food) => *food,
+            Condition::Survive(_, seconds) => *seconds,
+            Condition::BoardFilled(percent) => *percent,
+        }
+    }
+}
+
+// the title is the message "achievement.<id>"
+struct Achievement {
+    id: &'static str,
+    condition: Condition,
+}
+
+impl Achievement {
+    fn title(&self) -> String {
+        i18n::text(&format!("achievement.{}", self.id))
+    }
+}
+
+const ACHIEVEMENTS: [Achievement; 6] = [
+    Achievement { id: "speed-5-fast", condition: Condition::Speed(GameMode::FAST, 5) },
+    Achievement { id: "speed-10-fast", condition: Condition::Speed(GameMode::FAST, 10) },
+    Achievement { id: "food-50-no-pause", condition: Condition::FoodWithoutPause(50) },
+    Achievement { id: "survive-60-long", condition: Condition::Survive(GameMode::LONG, 60) },
+    Achievement { id: "survive-300-long", condition: Condition::Survive(GameMode::LONG, 300) },
+    Achievement { id: "fill-25", condition: Condition::BoardFilled(25) },
+];
+
+
+// per player: progress by achievement id
+type Progress = BTreeMap<String, BTreeMap<String, u32>>;
+
+fn load_progress() -> Progress {
+    local_storage()
+        .and_then(|local_storage| local_storage.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_progress(progress: &Progress) {
+    if let Some(local_storage) = local_storage() {
+        let json = serde_json::to_string(progress).unwrap();
+        if let Err(e) = local_storage.set_item(STORAGE_KEY, &json) {
+            log!("could not save achievements to local_storage: {:?}", e.as_string());
+        }
+    }
+}
+
+
+// Checks achievements against game events of a running game.
+pub struct Tracker {
+    name: String,
+    food_since_pause: u32,
+}
+
+impl Tracker {
+    pub const fn default() -> Self {
+        Self {
+            name: String::new(),
+            food_since_pause: 0,
+        }
+    }
+
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            food_since_pause: 0,
+        }
+    }
+
+    pub fn on_food_eaten(&mut self, world: &World) {
+        self.food_since_pause += 1;
+        let config = world.config();
+        let board_size = (config.columns * config.rows).max(1) as u32;
+        let filled_percent = world.snake().len() as u32 * 100 / board_size;
+        self.update(|condition| match condition {
+            Condition::Speed(game_mode, _) if *game_mode == world.game_mode() => Some(world.speed() as u32),
+            Condition::FoodWithoutPause(_) => Some(self.food_since_pause),
+            Condition::BoardFilled(_) => Some(filled_percent),
+            _ => None,
+        });
+    }
+
+    pub fn on_pause(&mut self) {
+        self.food_since_pause = 0;
+    }
+
+    pub fn on_game_over(&mut self, world: &World) {
+        let seconds = world.duration() / 1000;
+        self.update(|condition| match condition {
+            Condition::Survive(game_mode, _) if *game_mode == world.game_mode() => Some(seconds),
+            _ => None,
+        });
+    }
+
+    // stores the new progress where it is better than before, shows newly unlocked achievements
+    fn update<F: Fn(&Condition) -> Option<u32>>(&self, value_of: F) {
+        let mut progress = load_progress();
+        let player_progress = progress.entry(self.name.clone()).or_default();
+        let mut changed = false;
+        for achievement in &ACHIEVEMENTS {
+            let value = match value_of(&achievement.condition) {
+                Some(value) => value,
+                None => continue,
+            };
+            let stored = player_progress.entry(achievement.id.to_string()).or_insert(0);
+            if value > *stored {
+                let target = achievement.condition.target();
+                if *stored < target && value >= target {
+                    log!("achievement unlocked: {}", achievement.id);
+                    show_toast(&i18n::format("achievement.unlocked", &[&achievement.title()]));
+                }
+                *stored = value;
+                changed = true;
+            }
+        }
+        if changed {
+            save_progress(&progress);
+        }
+    }
+}
+
+
+fn show_toast(text: &str) {
+    let window = web_sys::window().unwrap();
+    let document = window.document().unwrap();
+    let toast = document.get_element_by_id("toast").unwrap().dyn_into::<web_sys::HtmlElement>().unwrap();
+    toast.set_text_content(Some(text));
+    toast.set_class_name("visible");
+    let callback = Closure::once_into_js(move || {
+        toast.set_class_name("");
+    });
+    let result = window.set_timeout_with_callback_and_timeout_and_arguments_0(callback.unchecked_ref(), TOAST_DURATION_MS);
+    if result.is_err() {
+        log!("could not hide toast");
+    }
+}
+
+pub fn print_achievements() {
+    let progress = load_progress();
+    let name = profile::selected_profile();
+    let player_progress = progress.get(&name);
+    let document = web_sys::window().unwrap().document().unwrap();
+    let list = document.get_element_by_id("achievements-list").unwrap();
+    list.set_inner_html("");
+    for achievement in &ACHIEVEMENTS {
+        let value = player_progress.and_then(|p| p.get(achievement.id)).copied().unwrap_or(0);
+        let result = print_achievement(&document, &list, achievement, value);
+        if result.is_err() {
+            log!("could not create achievement elements: {:?}", result.err().unwrap().as_string());
+            break;
+        }
+    }
+}
+
+fn print_achievement(
+        document: &web_sys::Document,
+        list: &web_sys::Element,
+        achievement: &Achievement,
+        value: u32)
+        -> Result<(), JsValue> {
+    let target = achievement.condition.target();
+    let value = value.min(target);
+    let div = document.create_element("div")?;
+    div.set_class_name(if value >= target {"achievement unlocked"} else {"achievement"});
+    let title = document.create_element("span")?;
+    title.set_text_content(Some(&achievement.title()));
+    let bar = document.create_element("progress")?;
+    bar.set_attribute("max", &target.to_string())?;
+    bar.set_attribute("value", &value.to_string())?;
+    let text = document.create_element("span")?;
+    text.set_text_content(Some(&format!("{}/{}", value, target)));
+    div.append_child(&title)?;
+    div.append_child(&bar)?;
+    div.append_child(&text)?;
+    list.append_child(&div)?;
+    Ok(())
+}
@@ -0,0 +1,156 @@
+// Computer-controlled snakes for the versus mode.
+// Like hazards they see the board only through closures and decide in ticks, so replays stay deterministic.
+
+use crate::world::Point;
+use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    // ticks until the snake notices where the food is now
+    fn reaction_delay(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 8,
+            Difficulty::Medium => 4,
+            Difficulty::Hard => 1,
+        }
+    }
+
+    // free cells a move has to leave reachable to count as safe
+    fn look_ahead(&self) -> usize {
+        match self {
+            Difficulty::Easy => 0,
+            Difficulty::Medium => 8,
+            Difficulty::Hard => 40,
+        }
+    }
+}
+
+
+#[derive(Clone)]
+pub struct AiSnake {
+    pub difficulty: Difficulty,
+    // head first, empty once the snake died
+    pub body: Vec<Point>,
+    pub score: u32,
+    pub alive: bool,
+    // index into the neighbours of the head
+    heading: usize,
+    // where the snake believes the food to be
+    target: Option<Point>,
+    ticks_until_look: u32,
+}
+
+impl AiSnake {
+    pub fn new(difficulty: Difficulty, body: Vec<Point>, heading: usize) -> Self {
+        Self {
+            difficulty,
+            body,
+            score: 0,
+            alive: true,
+            heading,
+            target: None,
+            ticks_until_look: 1,
+        }
+    }
+
+    // where the head moves next
+    pub fn next_head(&mut self, board: &Board, food: Point) -> Point {
+        self.ticks_until_look -= 1;
+        if self.ticks_until_look == 0 {
+            self.ticks_until_look = self.difficulty.reaction_delay();
+            self.target = Some(food);
+        }
+        if let Some(target) = self.target {
+            self.heading = self.decide(board, target);
+        }
+        (board.neighbours)(self.body[0])[self.heading]
+    }
+
+    // heads for the target on the shortest path, preferring moves that don't lead into a dead end
+    fn decide(&self, board: &Board, target: Point) -> usize {
+        let distances = board.distances_from(target);
+        let look_ahead = self.difficulty.look_ahead();
+        let mut best: Option<(usize, (bool, u32))> = None;
+        for (i, cell) in (board.neighbours)(self.body[0]).into_iter().enumerate() {
+            if !board.is_open(cell) {
+                continue;
+            }
+            let trapped = look_ahead > 0 && board.count_open_cells(cell, look_ahead) < look_ahead;
+            let distance = distances[board.index(cell)].unwrap_or(u32::MAX);
+            let rating = (trapped, distance);
+            if best.is_none_or(|(_, best_rating)| rating < best_rating) {
+                best = Some((i, rating));
+            }
+        }
+        best.map_or(self.heading, |(i, _)| i)
+    }
+}
+
+
+// what an AI snake knows about the board
+pub struct Board<'a> {
+    pub columns: i32,
+    pub rows: i32,
+    // the cells around a cell, always in the same order of directions
+    pub neighbours: &'a dyn Fn(Point) -> Vec<Point>,
+    // whether a snake may enter a cell on the board
+    pub is_free: &'a dyn Fn(Point) -> bool,
+}
+
+impl Board<'_> {
+    fn index(&self, point: Point) -> usize {
+        (point.y * self.columns + point.x) as usize
+    }
+
+    fn is_open(&self, point: Point) -> bool {
+        point.x >= 0 && point.y >= 0 && point.x < self.columns && point.y < self.rows && (self.is_free)(point)
+    }
+
+    // steps from `from` to every cell reachable through free cells (breadth first search)
+    fn distances_from(&self, from: Point) -> Vec<Option<u32>> {
+        let mut distances = vec![None; (self.columns * self.rows).max(0) as usize];
+        if !self.is_open(from) {
+            return distances;
+        }
+        distances[self.index(from)] = Some(0);
+        let mut queue = VecDeque::from([(from, 0)]);
+        while let Some((current, distance)) = queue.pop_front() {
+            for next in (self.neighbours)(current) {
+                if self.is_open(next) && distances[self.index(next)].is_none() {
+                    distances[self.index(next)] = Some(distance + 1);
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+        distances
+    }
+
+    // free cells reachable from `from`, counting stops at `limit`
+    fn count_open_cells(&self, from: Point, limit: usize) -> usize {
+        let mut seen = vec![false; (self.columns * self.rows).max(0) as usize];
+        seen[self.index(from)] = true;
+        let mut count = 1;
+        let mut queue = VecDeque::from([from]);
+        while let Some(current) = queue.pop_front() {
+            for next in (self.neighbours)(current) {
+                if count >= limit {
+                    return count;
+                }
+                if self.is_open(next) && !seen[self.index(next)] {
+                    seen[self.index(next)] = true;
+                    count += 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        count
+    }
+}
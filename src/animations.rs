@@ -0,0 +1,195 @@
+// Visual effects on top of the board: particle bursts, floating score pop-ups, screen shake and the
+// death animation. They run on frame time, not on game ticks, and are not part of the simulation,
+// so they use their own random numbers. Positions are in CSS pixels.
+
+use crate::rng::Rng;
+
+const BURST_PARTICLES: i32 = 16;
+// px/s
+const PARTICLE_SPEED_MIN: i32 = 40;
+const PARTICLE_SPEED_MAX: i32 = 160;
+const PARTICLE_GRAVITY: f64 = 200.0;
+// ms
+const PARTICLE_LIFE_MIN: i32 = 400;
+const PARTICLE_LIFE_MAX: i32 = 800;
+const PARTICLE_SIZE: f64 = 3.0;
+const POPUP_LIFE: f64 = 900.0;
+// px/s
+const POPUP_RISE_SPEED: f64 = 40.0;
+const SHAKE_DURATION: f64 = 300.0;
+// px
+const SHAKE_AMPLITUDE: f64 = 6.0;
+// ms between two segments disappearing
+const DEATH_SEGMENT_INTERVAL: f64 = 60.0;
+// ms the last particles of the death animation get to fade
+const DEATH_FADE_OUT: f64 = PARTICLE_LIFE_MAX as f64;
+// frames further apart are treated as a pause, so nothing jumps after the tab was in the background
+const MAX_FRAME_TIME: f64 = 100.0;
+
+
+struct Particle {
+    x: f64,
+    y: f64,
+    // px/s
+    vx: f64,
+    vy: f64,
+    // ms
+    age: f64,
+    life: f64,
+    colour: &'static str,
+}
+
+struct Popup {
+    x: f64,
+    y: f64,
+    text: String,
+    age: f64,
+}
+
+// segments of the dead snake disappear one after another, head first
+struct Death {
+    segments: Vec<((f64, f64), &'static str)>,
+    elapsed: f64,
+}
+
+impl Death {
+    fn hidden_segments(&self) -> usize {
+        ((self.elapsed / DEATH_SEGMENT_INTERVAL) as usize).min(self.segments.len())
+    }
+    fn is_done(&self) -> bool {
+        self.elapsed >= self.segments.len() as f64 * DEATH_SEGMENT_INTERVAL + DEATH_FADE_OUT
+    }
+}
+
+
+pub struct Animations {
+    enabled: bool,
+    rng: Rng,
+    particles: Vec<Particle>,
+    popups: Vec<Popup>,
+    // ms left
+    shake: f64,
+    death: Option<Death>,
+    timestamp_last_frame: Option<u32>,
+}
+
+impl Animations {
+    pub const fn new(enabled: bool, seed: u64) -> Self {
+        Self {
+            enabled,
+            rng: Rng::new(seed),
+            particles: Vec::new(),
+            popups: Vec::new(),
+            shake: 0.0,
+            death: None,
+            timestamp_last_frame: None,
+        }
+    }
+
+    // whether frames need to be drawn for the effects
+    pub fn is_active(&self) -> bool {
+        !self.particles.is_empty() || !self.popups.is_empty() || self.shake > 0.0
+            || self.death.as_ref().is_some_and(|death| !death.is_done())
+    }
+
+    // while the death animation runs, the game over text waits
+    pub fn is_dying(&self) -> bool {
+        self.death.as_ref().is_some_and(|death| death.hidden_segments() < death.segments.len())
+    }
+
+    // segments of the snake not to draw anymore, counted from the head
+    pub fn hidden_segments(&self) -> usize {
+        self.death.as_ref().map_or(0, |death| death.hidden_segments())
+    }
+
+    pub fn burst(&mut self, x: f64, y: f64, colour: &'static str) {
+        if !self.enabled {
+            return;
+        }
+        for _ in 0..BURST_PARTICLES {
+            let angle = self.rng.range(0, 360) as f64 * std::f64::consts::PI / 180.0;
+            let speed = self.rng.range(PARTICLE_SPEED_MIN, PARTICLE_SPEED_MAX) as f64;
+            let life = self.rng.range(PARTICLE_LIFE_MIN, PARTICLE_LIFE_MAX) as f64;
+            self.particles.push(Particle { x, y, vx: angle.cos() * speed, vy: angle.sin() * speed, age: 0.0, life, colour });
+        }
+    }
+
+    pub fn popup(&mut self, x: f64, y: f64, text: &str) {
+        if self.enabled {
+            self.popups.push(Popup { x, y, text: text.to_string(), age: 0.0 });
+        }
+    }
+
+    pub fn shake(&mut self) {
+        if self.enabled {
+            self.shake = SHAKE_DURATION;
+        }
+    }
+
+    // segments with their centre and colour, head first
+    pub fn start_death(&mut self, segments: Vec<((f64, f64), &'static str)>) {
+        if self.enabled {
+            self.death = Some(Death { segments, elapsed: 0.0 });
+        }
+    }
+
+    pub fn update(&mut self, timestamp: u32) {
+        let dt = match self.timestamp_last_frame {
+            Some(last) if timestamp > last => ((timestamp - last) as f64).min(MAX_FRAME_TIME),
+            _ => 0.0,
+        };
+        self.timestamp_last_frame = Some(timestamp);
+
+        for particle in &mut self.particles {
+            particle.age += dt;
+            particle.x += particle.vx * dt / 1000.0;
+            particle.y += particle.vy * dt / 1000.0;
+            particle.vy += PARTICLE_GRAVITY * dt / 1000.0;
+        }
+        self.particles.retain(|particle| particle.age < particle.life);
+        for popup in &mut self.popups {
+            popup.age += dt;
+            popup.y -= POPUP_RISE_SPEED * dt / 1000.0;
+        }
+        self.popups.retain(|popup| popup.age < POPUP_LIFE);
+        self.shake = (self.shake - dt).max(0.0);
+
+        let mut exploded = Vec::new();
+        if let Some(death) = &mut self.death {
+            let hidden_before = death.hidden_segments();
+            death.elapsed += dt;
+            exploded = death.segments[hidden_before..death.hidden_segments()].to_vec();
+        }
+        for ((x, y), colour) in exploded {
+            self.burst(x, y, colour);
+        }
+    }
+
+    // offset of the whole board while shaking, smaller towards the end
+    pub fn shake_offset(&mut self) -> (f64, f64) {
+        if self.shake <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let amplitude = SHAKE_AMPLITUDE * self.shake / SHAKE_DURATION;
+        let dx = self.rng.range(-100, 101) as f64 / 100.0 * amplitude;
+        let dy = self.rng.range(-100, 101) as f64 / 100.0 * amplitude;
+        (dx, dy)
+    }
+
+    pub fn draw(&self, context: &web_sys::CanvasRenderingContext2d) {
+        for particle in &self.particles {
+            context.set_global_alpha(1.0 - particle.age / particle.life);
+            context.set_fill_style_str(particle.colour);
+            context.fill_rect(particle.x - PARTICLE_SIZE / 2.0, particle.y - PARTICLE_SIZE / 2.0, PARTICLE_SIZE, PARTICLE_SIZE);
+        }
+        context.set_font("bold 16px sans-serif");
+        context.set_text_align("center");
+        context.set_fill_style_str("#000");
+        for popup in &self.popups {
+            context.set_global_alpha(1.0 - popup.age / POPUP_LIFE);
+            let result = context.fill_text(&popup.text, popup.x, popup.y);
+            crate::utils::handle_js_error(result);
+        }
+        context.set_global_alpha(1.0);
+    }
+}
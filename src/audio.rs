@@ -0,0 +1,190 @@
+// Playback of the sounds rendered by synth through WebAudio, with volumes kept in localStorage.
+// The audio context is created with the first sound, as browsers only allow audio once the player
+// interacted with the page.
+
+use crate::utils::log;
+use crate::utils::local_storage;
+use crate::synth;
+use crate::synth::Effect;
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "audio";
+
+
+// volumes in [0, 1]
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub effects: f32,
+    pub music: f32,
+    pub muted: bool,
+}
+
+impl AudioSettings {
+    pub const fn default() -> Self {
+        Self {
+            master: 0.8,
+            effects: 0.8,
+            music: 0.4,
+            muted: false,
+        }
+    }
+}
+
+pub fn load_settings() -> AudioSettings {
+    local_storage()
+        .and_then(|local_storage| local_storage.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or(AudioSettings::default())
+}
+
+pub fn save_settings(settings: &AudioSettings) {
+    if let Some(local_storage) = local_storage() {
+        let json = serde_json::to_string(settings).unwrap();
+        if let Err(e) = local_storage.set_item(STORAGE_KEY, &json) {
+            log!("could not save audio settings to local_storage: {:?}", e.as_string());
+        }
+    }
+}
+
+
+// effects and music go through their own volume, then through the master volume
+struct Nodes {
+    context: web_sys::AudioContext,
+    master: web_sys::GainNode,
+    effects: web_sys::GainNode,
+    music: web_sys::GainNode,
+    music_buffer: web_sys::AudioBuffer,
+}
+
+pub struct Audio {
+    settings: AudioSettings,
+    nodes: Option<Nodes>,
+    // playing while there is one
+    music_source: Option<web_sys::AudioBufferSourceNode>,
+    pitch: f32,
+}
+
+impl Audio {
+    pub const fn new() -> Self {
+        Self {
+            settings: AudioSettings::default(),
+            nodes: None,
+            music_source: None,
+            pitch: 1.0,
+        }
+    }
+
+    pub fn set_settings(&mut self, settings: AudioSettings) {
+        self.settings = settings;
+        if let Some(nodes) = &self.nodes {
+            self.apply_volumes(nodes);
+        }
+    }
+
+    fn apply_volumes(&self, nodes: &Nodes) {
+        let master = if self.settings.muted {0.0} else {self.settings.master};
+        nodes.master.gain().set_value(master);
+        nodes.effects.gain().set_value(self.settings.effects);
+        nodes.music.gain().set_value(self.settings.music);
+    }
+
+    fn nodes(&mut self) -> Option<&Nodes> {
+        if self.nodes.is_none() {
+            match create_nodes() {
+                Ok(nodes) => {
+                    self.apply_volumes(&nodes);
+                    self.nodes = Some(nodes);
+                },
+                Err(e) => {
+                    log!("could not set up audio: {:?}", e.as_string());
+                    return None;
+                },
+            }
+        }
+        let nodes = self.nodes.as_ref()?;
+        if nodes.context.state() == web_sys::AudioContextState::Suspended {
+            let _ = nodes.context.resume();
+        }
+        Some(nodes)
+    }
+
+    pub fn play(&mut self, effect: Effect) {
+        if self.settings.muted {
+            return;
+        }
+        let pitch = self.pitch;
+        if let Some(nodes) = self.nodes() {
+            let samples = synth::render_effect(effect, pitch, nodes.context.sample_rate());
+            let result = buffer(&nodes.context, &samples)
+                .and_then(|buffer| start_source(&nodes.context, &buffer, &nodes.effects, false, 1.0));
+            crate::utils::handle_js_error(result.map(|_| ()));
+        }
+    }
+
+    // starts or stops the music loop, nothing happens if it already is that way
+    pub fn set_music(&mut self, playing: bool) {
+        if !playing {
+            if let Some(source) = self.music_source.take() {
+                let source: &web_sys::AudioScheduledSourceNode = source.as_ref();
+                crate::utils::handle_js_error(source.stop());
+            }
+            return;
+        }
+        if self.music_source.is_some() || self.settings.muted {
+            return;
+        }
+        let pitch = self.pitch;
+        if let Some(nodes) = self.nodes() {
+            match start_source(&nodes.context, &nodes.music_buffer, &nodes.music, true, pitch) {
+                Ok(source) => self.music_source = Some(source),
+                Err(e) => {
+                    log!("could not play music: {:?}", e.as_string());
+                },
+            }
+        }
+    }
+
+    // higher pitch and faster music with rising speed
+    pub fn set_speed(&mut self, speed: i32) {
+        self.pitch = synth::pitch(speed);
+        if let Some(source) = &self.music_source {
+            source.playback_rate().set_value(self.pitch);
+        }
+    }
+}
+
+fn create_nodes() -> Result<Nodes, wasm_bindgen::JsValue> {
+    let context = web_sys::AudioContext::new()?;
+    let master = context.create_gain()?;
+    master.connect_with_audio_node(&context.destination())?;
+    let effects = context.create_gain()?;
+    effects.connect_with_audio_node(&master)?;
+    let music = context.create_gain()?;
+    music.connect_with_audio_node(&master)?;
+    let music_buffer = buffer(&context, &synth::render_music(context.sample_rate()))?;
+    Ok(Nodes { context, master, effects, music, music_buffer })
+}
+
+fn buffer(context: &web_sys::AudioContext, samples: &[f32]) -> Result<web_sys::AudioBuffer, wasm_bindgen::JsValue> {
+    let buffer = context.create_buffer(1, samples.len().max(1) as u32, context.sample_rate())?;
+    buffer.copy_to_channel(samples, 0)?;
+    Ok(buffer)
+}
+
+fn start_source(
+        context: &web_sys::AudioContext,
+        buffer: &web_sys::AudioBuffer,
+        output: &web_sys::GainNode,
+        looping: bool,
+        rate: f32)
+        -> Result<web_sys::AudioBufferSourceNode, wasm_bindgen::JsValue> {
+    let source = context.create_buffer_source()?;
+    source.set_buffer(Some(buffer));
+    source.set_loop(looping);
+    source.playback_rate().set_value(rate);
+    source.connect_with_audio_node(output)?;
+    let scheduled: &web_sys::AudioScheduledSourceNode = source.as_ref();
+    scheduled.start()?;
+    Ok(source)
+}
@@ -0,0 +1,60 @@
+// The part of the board shown when the board is larger than the canvas. The view follows a focus,
+// the head of the snake, and scrolls there smoothly. Positions are in CSS pixels of the whole board.
+
+// ms for the view to cover about two thirds of the way to the focus
+const SMOOTHING_TIME: f64 = 150.0;
+// closer than this the view counts as arrived
+const SETTLE_DISTANCE: f64 = 0.5;
+const MAX_FRAME_TIME: f64 = 100.0;
+
+
+pub struct Camera {
+    // top left corner of the view
+    pub x: f64,
+    pub y: f64,
+    target: (f64, f64),
+    timestamp_last_frame: Option<u32>,
+}
+
+impl Camera {
+    pub const fn new() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            target: (0.0, 0.0),
+            timestamp_last_frame: None,
+        }
+    }
+
+    // centres the view on the focus as far as the board allows
+    pub fn follow(&mut self, focus: (f64, f64), view: (f64, f64), board: (f64, f64)) {
+        self.target = (
+            (focus.0 - view.0 / 2.0).clamp(0.0, (board.0 - view.0).max(0.0)),
+            (focus.1 - view.1 / 2.0).clamp(0.0, (board.1 - view.1).max(0.0)),
+        );
+    }
+
+    pub fn jump(&mut self) {
+        (self.x, self.y) = self.target;
+    }
+
+    // moves the view towards the focus, all the way if it shouldn't scroll
+    pub fn update(&mut self, timestamp: u32, scroll: bool) {
+        let dt = match self.timestamp_last_frame {
+            Some(last) if timestamp > last => ((timestamp - last) as f64).min(MAX_FRAME_TIME),
+            _ => 0.0,
+        };
+        self.timestamp_last_frame = Some(timestamp);
+        if !scroll || !self.is_moving() {
+            self.jump();
+            return;
+        }
+        let share = 1.0 - (-dt / SMOOTHING_TIME).exp();
+        self.x += (self.target.0 - self.x) * share;
+        self.y += (self.target.1 - self.y) * share;
+    }
+
+    pub fn is_moving(&self) -> bool {
+        (self.target.0 - self.x).abs() > SETTLE_DISTANCE || (self.target.1 - self.y).abs() > SETTLE_DISTANCE
+    }
+}
@@ -0,0 +1,265 @@
+// Levels of the campaign and the player's progress through them.
+// Layouts: '#' wall, 'S' start of the snake's head (moving down),
+// '1' to '9' portals connecting the two cells with the same digit,
+// 'P' horizontal and 'V' vertical patrol, 'B' bouncer, 'H' hunter, anything else free.
+
+use crate::utils::log;
+use crate::utils::local_storage;
+
+use wasm_bindgen::prelude::*;
+
+const STORAGE_KEY: &str = "campaign";
+
+
+pub struct Level {
+    // the name is the message "level.<id>"
+    pub id: &'static str,
+    pub layout: &'static [&'static str],
+    pub start_speed: i32,
+    pub food_target: u32,
+    // ms
+    pub time_limit: Option<u32>,
+}
+
+impl Level {
+    pub fn name(&self) -> String {
+        crate::i18n::text(&format!("level.{}", self.id))
+    }
+    pub fn columns(&self) -> i32 {
+        self.layout[0].len() as i32
+    }
+    pub fn rows(&self) -> i32 {
+        self.layout.len() as i32
+    }
+}
+
+pub const LEVELS: [Level; 7] = [
+    Level {
+        id: "open-field",
+        layout: &[
+            "########################",
+            "#......................#",
+            "#......................#",
+            "#......................#",
+            "#......................#",
+            "#..........S...........#",
+            "#......................#",
+            "#......................#",
+            "#......................#",
+            "#......................#",
+            "#......................#",
+            "#......................#",
+            "#......................#",
+            "#......................#",
+            "#......................#",
+            "########################",
+        ],
+        start_speed: 1,
+        food_target: 5,
+        time_limit: None,
+    },
+    Level {
+        id: "pillars",
+        layout: &[
+            "########################",
+            "#......................#",
+            "#......................#",
+            "#...##.....S.....##....#",
+            "#...##...........##....#",
+            "#......................#",
+            "#......................#",
+            "#.........####.........#",
+            "#.........####.........#",
+            "#......................#",
+            "#......................#",
+            "#...##...........##....#",
+            "#...##...........##....#",
+            "#......................#",
+            "#......................#",
+            "########################",
+        ],
+        start_speed: 2,
+        food_target: 8,
+        time_limit: None,
+    },
+    Level {
+        id: "corridors",
+        layout: &[
+            "########################",
+            "#......................#",
+            "#......................#",
+            "#..........S...........#",
+            "#######..........#######",
+            "#......................#",
+            "#......................#",
+            "#....##############....#",
+            "#......................#",
+            "#......................#",
+            "#######..........#######",
+            "#......................#",
+            "#......................#",
+            "#....##############....#",
+            "#......................#",
+            "########################",
+        ],
+        start_speed: 3,
+        food_target: 10,
+        time_limit: Some(90 * 1000),
+    },
+    Level {
+        id: "cross",
+        layout: &[
+            "########################",
+            "#..........#...........#",
+            "#..........#...........#",
+            "#..S.......#...........#",
+            "#..........#...........#",
+            "#......................#",
+            "#......................#",
+            "#....##############....#",
+            "#......................#",
+            "#......................#",
+            "#..........#...........#",
+            "#..........#...........#",
+            "#..........#...........#",
+            "#..........#...........#",
+            "#..........#...........#",
+            "########################",
+        ],
+        start_speed: 3,
+        food_target: 12,
+        time_limit: Some(120 * 1000),
+    },
+    Level {
+        id: "labyrinth",
+        layout: &[
+            "########################",
+            "#......#.......#.......#",
+            "#......#.......#.......#",
+            "#S.....#...#...#...#...#",
+            "#..........#.......#...#",
+            "#..........#.......#...#",
+            "######.....#####...#...#",
+            "#..........#.......#...#",
+            "#..........#.......#...#",
+            "#...########...#####...#",
+            "#..............#.......#",
+            "#..............#.......#",
+            "#...#......#...#...#####",
+            "#...#......#...........#",
+            "#...#......#...........#",
+            "########################",
+        ],
+        start_speed: 4,
+        food_target: 15,
+        time_limit: Some(120 * 1000),
+    },
+    Level {
+        id: "wormholes",
+        layout: &[
+            "########################",
+            "#..........#...........#",
+            "#..1.......#.......2...#",
+            "#..........#...........#",
+            "#..........#...........#",
+            "#..........#...........#",
+            "#####S######...#########",
+            "#..........#...........#",
+            "#..........#...........#",
+            "#..........#...........#",
+            "#..........#...........#",
+            "#..........#...........#",
+            "#..2.......#.......1...#",
+            "#..........#...........#",
+            "#..........#...........#",
+            "########################",
+        ],
+        start_speed: 3,
+        food_target: 12,
+        time_limit: None,
+    },
+    Level {
+        id: "hunted",
+        layout: &[
+            "########################",
+            "#......................#",
+            "#....V.................#",
+            "#..........S...........#",
+            "#......................#",
+            "#..P...................#",
+            "#......................#",
+            "#.....##.........##....#",
+            "#.....##....B....##....#",
+            "#......................#",
+            "#......................#",
+            "#.................P....#",
+            "#......................#",
+            "#......................#",
+            "#H.....................#",
+            "########################",
+        ],
+        start_speed: 3,
+        food_target: 15,
+        time_limit: None,
+    },
+];
+
+
+// index of the highest level the player may play
+pub fn unlocked_level() -> usize {
+    local_storage()
+        .and_then(|local_storage| local_storage.get_item(STORAGE_KEY).ok().flatten())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0)
+        .min(LEVELS.len() - 1)
+}
+
+// unlocks the level after the completed one, the completed one stays selected for a retry
+pub fn complete_level(level: usize) {
+    let next_level = (level + 1).min(LEVELS.len() - 1);
+    if next_level > unlocked_level() {
+        if let Some(local_storage) = local_storage() {
+            crate::utils::handle_js_error(local_storage.set_item(STORAGE_KEY, &next_level.to_string()));
+        }
+    }
+    print_levels(level);
+}
+
+// index of the level chosen in the level select
+pub fn selected_level() -> usize {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let select = document.get_element_by_id("campaign-level").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    select.value().parse().unwrap_or(0)
+}
+
+pub fn print_levels(selected: usize) {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let select = document.get_element_by_id("campaign-level").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    while select.child_element_count() > 0 {
+        select.last_element_child().unwrap().remove();
+    }
+    for (i, level) in LEVELS.iter().enumerate().take(unlocked_level() + 1) {
+        let result = print_option(&document, &select, i, level, i == selected);
+        if result.is_err() {
+            log!("could not create level option: {:?}", result.err().unwrap().as_string());
+            break;
+        }
+    }
+}
+
+fn print_option(
+        document: &web_sys::Document,
+        select: &web_sys::HtmlSelectElement,
+        index: usize,
+        level: &Level,
+        selected: bool)
+        -> Result<(), JsValue> {
+    let option = document.create_element("option")?;
+    option.set_attribute("value", &index.to_string())?;
+    option.set_text_content(Some(&format!("{}: {}", index + 1, level.name())));
+    if selected {
+        option.set_attribute("selected", "")?;
+    }
+    select.append_child(&option)?;
+    Ok(())
+}
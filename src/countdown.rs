@@ -0,0 +1,70 @@
+// The 3-2-1 before a game starts and before it goes on after a pause. The world doesn't tick while it
+// runs: a new game only starts its clock with its first tick, and a paused game stays paused until the
+// countdown is over, so the countdown never counts toward the duration.
+
+const NUMBER_COLOUR: &str = "rgba(0, 0, 0, 0.6)";
+const OUTLINE_COLOUR: &str = "#FFF";
+const MIN_SIZE: f64 = 24.0;
+const MAX_SIZE: f64 = 160.0;
+
+
+// what happens when the countdown is over
+#[derive(Copy, Clone, PartialEq)]
+pub enum Then {
+    Start,
+    Resume,
+}
+
+pub struct Countdown {
+    // ms
+    length: u32,
+    then: Then,
+    timestamp_start: Option<u32>,
+    number: u32,
+}
+
+impl Countdown {
+    pub fn new(seconds: u32, then: Then) -> Self {
+        Self {
+            length: seconds * 1000,
+            then,
+            timestamp_start: None,
+            number: seconds,
+        }
+    }
+
+    pub fn then(&self) -> Then {
+        self.then
+    }
+
+    // the number shown, counting down from the seconds to 1
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.timestamp_start.is_some()
+    }
+
+    // false once the countdown is over; it starts with the first update
+    pub fn update(&mut self, timestamp: u32) -> bool {
+        let start = *self.timestamp_start.get_or_insert(timestamp);
+        let left = self.length.saturating_sub(timestamp.saturating_sub(start));
+        self.number = left.div_ceil(1000);
+        left > 0
+    }
+
+    pub fn draw(&self, context: &web_sys::CanvasRenderingContext2d, width: f64, height: f64) {
+        let size = (width.min(height) / 3.0).clamp(MIN_SIZE, MAX_SIZE);
+        context.set_text_align("center");
+        context.set_text_baseline("middle");
+        context.set_font(&format!("bold {}px sans-serif", size.round()));
+        context.set_line_width(size / 20.0);
+        context.set_stroke_style_str(OUTLINE_COLOUR);
+        context.set_fill_style_str(NUMBER_COLOUR);
+        let text = self.number.to_string();
+        crate::utils::handle_js_error(context.stroke_text(&text, width / 2.0, height / 2.0));
+        crate::utils::handle_js_error(context.fill_text(&text, width / 2.0, height / 2.0));
+        context.set_text_baseline("alphabetic");
+    }
+}
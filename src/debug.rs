@@ -0,0 +1,143 @@
+// Overlay for tuning speeds and chasing input problems, toggled with F3: render and tick rates, how late
+// ticks come, and the state of the snake. Pressing F3 again adds the coordinates of the cells, a third
+// time hides it. Only meant for developers, so the texts aren't translated.
+
+use std::collections::VecDeque;
+
+// ms the rates are counted over
+const RATE_WINDOW: u32 = 1000;
+// a tick coming later than this after it was due counts as late, about a frame at 60 Hz
+const LATE_TOLERANCE: u32 = 20;
+const PANEL_COLOUR: &str = "rgba(0, 0, 0, 0.7)";
+const TEXT_COLOUR: &str = "#0F0";
+const COORDINATES_COLOUR: &str = "#888";
+const FONT_SIZE: f64 = 12.0;
+const LINE_HEIGHT: f64 = 15.0;
+const PANEL_WIDTH: f64 = 230.0;
+const MARGIN: f64 = 8.0;
+// smaller cells don't get coordinates
+pub const MIN_COORDINATES_BLOCK_SIZE: u32 = 18;
+
+
+#[derive(Copy, Clone, PartialEq)]
+enum Mode {
+    Hidden,
+    Stats,
+    Coordinates,
+}
+
+pub struct DebugOverlay {
+    mode: Mode,
+    // timestamps within the last RATE_WINDOW
+    frames: VecDeque<u32>,
+    ticks: VecDeque<u32>,
+    timestamp_last_tick: Option<u32>,
+    // ms the last tick came after it was due
+    last_lateness: u32,
+    late_ticks: u32,
+    // ticks that would have fit into the delays; the world doesn't catch up on them
+    dropped_ticks: u32,
+}
+
+impl DebugOverlay {
+    pub const fn new() -> Self {
+        Self {
+            mode: Mode::Hidden,
+            frames: VecDeque::new(),
+            ticks: VecDeque::new(),
+            timestamp_last_tick: None,
+            last_lateness: 0,
+            late_ticks: 0,
+            dropped_ticks: 0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.mode = match self.mode {
+            Mode::Hidden => Mode::Stats,
+            Mode::Stats => Mode::Coordinates,
+            Mode::Coordinates => Mode::Hidden,
+        };
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.mode != Mode::Hidden
+    }
+
+    pub fn shows_coordinates(&self) -> bool {
+        self.mode == Mode::Coordinates
+    }
+
+    // counts start over with a new game
+    pub fn reset(&mut self) {
+        self.ticks.clear();
+        self.timestamp_last_tick = None;
+        self.last_lateness = 0;
+        self.late_ticks = 0;
+        self.dropped_ticks = 0;
+    }
+
+    // after the world was held up on purpose, like during a countdown, the next tick isn't late
+    pub fn skip_timing(&mut self) {
+        self.timestamp_last_tick = None;
+    }
+
+    pub fn on_frame(&mut self, timestamp: u32) {
+        push_timestamp(&mut self.frames, timestamp);
+    }
+
+    // `threshold` is the ms between ticks at the time, None for ticks that wait for input
+    pub fn on_tick(&mut self, timestamp: u32, threshold: Option<u32>) {
+        push_timestamp(&mut self.ticks, timestamp);
+        if let (Some(last), Some(threshold)) = (self.timestamp_last_tick, threshold) {
+            self.last_lateness = timestamp.saturating_sub(last).saturating_sub(threshold);
+            if self.last_lateness > LATE_TOLERANCE {
+                self.late_ticks += 1;
+                self.dropped_ticks += self.last_lateness / threshold.max(1);
+            }
+        }
+        self.timestamp_last_tick = threshold.map(|_| timestamp);
+    }
+
+    pub fn timing_lines(&self) -> Vec<String> {
+        vec![
+            format!("fps: {}", self.frames.len()),
+            format!("ticks/s: {}", self.ticks.len()),
+            format!("late ticks: {} (last {} ms)", self.late_ticks, self.last_lateness),
+            format!("dropped ticks: {}", self.dropped_ticks),
+        ]
+    }
+
+    // a panel in the top left corner of the canvas
+    pub fn draw(&self, context: &web_sys::CanvasRenderingContext2d, lines: &[String]) {
+        context.set_fill_style_str(PANEL_COLOUR);
+        context.fill_rect(MARGIN, MARGIN, PANEL_WIDTH, LINE_HEIGHT * lines.len() as f64 + MARGIN);
+        context.set_font(&format!("{}px monospace", FONT_SIZE));
+        context.set_text_align("left");
+        context.set_text_baseline("top");
+        context.set_fill_style_str(TEXT_COLOUR);
+        for (i, line) in lines.iter().enumerate() {
+            crate::utils::handle_js_error(context.fill_text(line, MARGIN * 1.5, MARGIN * 1.5 + i as f64 * LINE_HEIGHT));
+        }
+        context.set_text_baseline("alphabetic");
+    }
+
+    // "x,y" centred on each cell, given as x, y and its centre in board coordinates
+    pub fn draw_coordinates(&self, context: &web_sys::CanvasRenderingContext2d, cells: &[(i32, i32, (f64, f64))], block_size: u32) {
+        context.set_font(&format!("{}px monospace", (block_size as f64 / 3.5).round()));
+        context.set_text_align("center");
+        context.set_text_baseline("middle");
+        context.set_fill_style_str(COORDINATES_COLOUR);
+        for (x, y, (center_x, center_y)) in cells {
+            crate::utils::handle_js_error(context.fill_text(&format!("{},{}", x, y), *center_x, *center_y));
+        }
+        context.set_text_baseline("alphabetic");
+    }
+}
+
+fn push_timestamp(timestamps: &mut VecDeque<u32>, timestamp: u32) {
+    timestamps.push_back(timestamp);
+    while timestamps.front().is_some_and(|first| timestamp.saturating_sub(*first) >= RATE_WINDOW) {
+        timestamps.pop_front();
+    }
+}
@@ -1,48 +1,61 @@
 use crate::utils::log;
-use crate::utils::random;
 use crate::textdisplay::update_text_display;
 use crate::textdisplay::update_speed_display;
 use crate::textdisplay::update_duration_display;
+use crate::textdisplay::update_time_left_display;
+use crate::textdisplay::update_goal_display;
+use crate::textdisplay::update_hunger_display;
+use crate::textdisplay::update_effects_display;
+use crate::textdisplay::update_touch_controls_display;
+use crate::textdisplay::update_opponents_display;
+use crate::textdisplay::update_puzzle_help_display;
 use crate::textdisplay::set_background_colour;
 use crate::highscore;
+use crate::profile;
+use crate::achievements;
+use crate::campaign;
+use crate::puzzle;
+use crate::hazards::HazardKind;
+use crate::powerups;
+use crate::powerups::PowerUpKind;
+use crate::hex;
+use crate::layout;
+use crate::animations::Animations;
+use crate::camera::Camera;
+use crate::countdown::{Countdown, Then};
+use crate::debug::{self, DebugOverlay};
+use crate::audio::{Audio, AudioSettings};
+use crate::synth::Effect;
+use crate::menu::{Menu, MenuInput, MenuItem, Screen};
+use crate::utils;
+use crate::i18n;
+use crate::palette::Palette;
+use crate::textdisplay::announce;
+use crate::world::{BoardType, DeathCause, GameMode, Point, Tick, TickEvents, World, WorldConfig, DEFAULT_INPUT, HUNGER_TICKS, INITIAL_SNAKE_LEN, VERSUS_TARGET_LENGTH};
 
 use wasm_bindgen::prelude::*;
 
 
-pub const DEFAULT_INPUT: char = '1';
-pub const INPUT_DOWN: char = 's';
-const INITIAL_SNAKE_LEN: usize = 3;
-const FRAME_RATE_SPEED_1: i32 = 1000 / 10;
-const SPEED_INCREASE_MS_MODE_KEYBOARD: i32 = 10;
-const SPEED_INCREASE_MS_MODE_TOUCH: i32 = 5;
-const SPEED_INCREASE_AT_SCORE: u32 = 3;
 const SPEED_TO_SET_BG_COL: i32 = 2;
-const TOUCH_MODE_FOOD_BORDER_OFFSET: u32 = 5;
-
-const COLOURS: [&str; 21] = [
-    "#050",
-    "#0A0",
-    "#0F0",
-    "#00F",
-    "#00A",
-    "#005",
-    "#055",
-    "#0AA",
-    "#0FF",
-    "#F0F",
-    "#A0A",
-    "#505",
-    "#500",
-    "#A00",
-    "#F00",
-    "#FA0",
-    "#A50",
-    "#A30",
-    "#550",
-    "#AA0",
-    "#FF0",
+// largest side of the minimap in px, and at most this share of the canvas
+const MINIMAP_SIZE: f64 = 150.0;
+const MINIMAP_SHARE: f64 = 0.3;
+const MINIMAP_MARGIN: f64 = 8.0;
+const WALL_COLOUR: &str = "#444";
+const HAZARD_COLOUR_PATROL: &str = "#800";
+const HAZARD_COLOUR_BOUNCER: &str = "#F60";
+const HAZARD_COLOUR_HUNTER: &str = "#609";
+const PORTAL_COLOURS: [&str; 4] = [
+    "#80F",
+    "#F80",
+    "#08F",
+    "#F08",
 ];
 
+fn lerp(from: (f64, f64), to: (f64, f64), fraction: f64) -> (f64, f64) {
+    (from.0 + (to.0 - from.0) * fraction, from.1 + (to.1 - from.1) * fraction)
+}
+
 const BACKGROUND_COLOURS: [&str; 7] = [
     "#00F",
     "#0FF",
@@ -53,51 +66,77 @@ const BACKGROUND_COLOURS: [&str; 7] = [
     "#0F0",
 ];
 
-#[derive(Copy, Clone, PartialEq)]
-struct Point {
-    x: i32,
-    y: i32,
+#[derive(Copy, Clone)]
+enum FoodShape {
+    Circle,
+    Square,
+    Triangle,
+    Diamond,
 }
 
-#[derive(PartialEq)]
-enum Direction {
-    UP,
-    DOWN,
-    LEFT,
-    RIGHT,
-}
+const FOOD_SHAPES: [FoodShape; 4] = [FoodShape::Circle, FoodShape::Square, FoodShape::Triangle, FoodShape::Diamond];
 
-
-#[derive(PartialEq)]
-pub enum GameMode {
-    FAST,
-    LONG,
+// how the game is shown, from the form
+pub struct DisplaySettings {
+    // the actual block_size follows the viewport
+    pub max_block_size: u32,
+    pub draw_grid: bool,
+    pub animations: bool,
+    // the snake is drawn between its last and current cells
+    pub smooth: bool,
+    pub palette: Palette,
+    // food gets a shape besides its colour
+    pub food_shapes: bool,
+    // no flashing background and no shaking board
+    pub reduced_motion: bool,
+    // seconds before a game starts or goes on after a pause, 0 for none
+    pub countdown: u32,
 }
 
+impl DisplaySettings {
+    pub const fn default() -> Self {
+        Self {
+            max_block_size: 1,
+            draw_grid: false,
+            animations: false,
+            smooth: false,
+            palette: Palette::Standard,
+            food_shapes: false,
+            reduced_motion: false,
+            countdown: 0,
+        }
+    }
+}
 
 pub struct Game {
+    // of the canvas
     width: u32,
     height: u32,
+    // of the whole board, larger than the canvas when scrolling
+    board_width: u32,
+    board_height: u32,
     block_size: u32,
-    draw_grid: bool,
-    speed: i32,
-    score: u32,
+    settings: DisplaySettings,
     context: Option<web_sys::CanvasRenderingContext2d>,
-    snake: Vec<Point>,
-    direction: Direction,
-    food: Point,
-    over: bool,
-    pause: bool,
-    input: char,
-    pressed: bool,
-    timestamp_last_frame: u32,
+    world: World,
     colour_index: usize,
-    touch_mode: bool,
-    game_mode: GameMode,
     name: String,
-    timestamp_game_start: u32,
-    timestamp_pause_start: u32,
-    pause_duration_sum: u32,
+    achievements: achievements::Tracker,
+    animations: Animations,
+    // to show the points of the last food
+    last_weighted_score: u32,
+    menu: Option<Menu>,
+    countdown: Option<Countdown>,
+    debug: DebugOverlay,
+    // whether the render loop is running
+    looping: bool,
+    previous_snake: Vec<Point>,
+    timestamp_last_move: u32,
+    timestamp_frame: u32,
+    camera: Camera,
+    // walls of the whole board, a pixel per cell, for the minimap of boards larger than the canvas
+    minimap_walls: Option<web_sys::HtmlCanvasElement>,
+    audio: Audio,
 }
 
 
@@ -106,361 +145,599 @@ impl Game {
         Self {
             width: 1,
             height: 1,
+            board_width: 1,
+            board_height: 1,
             block_size: 1,
-            draw_grid: false,
-            speed: 0,
-            score: 0,
+            settings: DisplaySettings::default(),
             context: None,
-            snake: Vec::new(),
-            direction: Direction::DOWN,
-            food: Point{x:0,y:0},
-            over: true,
-            pause: false,
-            input: DEFAULT_INPUT,
-            pressed: false,
-            timestamp_last_frame: 0,
+            world: World::default(),
             colour_index: 0,
-            touch_mode: false,
-            game_mode: GameMode::FAST,
             name: String::new(),
-            timestamp_game_start: 0,
-            timestamp_pause_start: 0,
-            pause_duration_sum: 0,
+            achievements: achievements::Tracker::default(),
+            animations: Animations::new(false, 0),
+            last_weighted_score: 0,
+            menu: None,
+            countdown: None,
+            debug: DebugOverlay::new(),
+            looping: false,
+            previous_snake: Vec::new(),
+            timestamp_last_move: 0,
+            timestamp_frame: 0,
+            camera: Camera::new(),
+            minimap_walls: None,
+            audio: Audio::new(),
         }
     }
     pub fn set_state(
         &mut self, 
-        width: u32, 
-        height: u32, 
-        block_size: u32, 
-        draw_grid: bool, 
-        touch_mode: bool,
-        game_mode: GameMode,
+        settings: DisplaySettings,
         name: &str,
+        config: WorldConfig,
         context: web_sys::CanvasRenderingContext2d)
     {
-        log!("  re-setting game state! width: {}, height: {}, block_size: {}, draw_grid: {}, touch_mode: {}", 
-            width, height, block_size, draw_grid, touch_mode);
-
-        self.width = width;
-        self.height = height;
-        self.block_size = block_size;
-        self.draw_grid = draw_grid;
-        self.touch_mode = touch_mode;
-        self.game_mode = game_mode;
+        log!("  re-setting game state! columns: {}, rows: {}, max_block_size: {}, draw_grid: {}, touch_mode: {}, seed: {}", 
+            config.columns, config.rows, settings.max_block_size, settings.draw_grid, config.touch_mode, config.seed);
+
         self.name = name.to_string();
-        self.speed = 1;
-        self.score = 0;
         self.context = Some(context);
-        self.snake = self.init_snake();
-        self.direction = Direction::DOWN;
-        self.over = false;
-        self.pause = false;
-        self.input = INPUT_DOWN;
-        self.pressed = false;
-        self.timestamp_last_frame = 0;
         self.colour_index = 0;
-        self.timestamp_game_start = 0;
-        self.timestamp_pause_start = 0;
-        self.pause_duration_sum = 0;
+        self.achievements = achievements::Tracker::new(name);
+        self.world = World::new(config);
+        self.animations = Animations::new(settings.animations, utils::random_seed());
+        self.last_weighted_score = 0;
+        self.menu = None;
+        self.looping = true;
+        self.previous_snake = Vec::new();
+        self.settings = settings;
+        self.countdown = self.new_countdown(Then::Start);
+        self.debug.reset();
+        self.minimap_walls = None;
+        self.audio.set_speed(self.world.speed());
+        self.relayout();
         set_background_colour("#FFF");
-        update_text_display(self.score, self.speed as u32);
-        self.place_food();
+        update_text_display(self.world.score(), self.world.weighted_score(), self.world.speed() as u32);
+        update_goal_display(self.goal_text().as_deref());
+        update_hunger_display(self.world.hunger(), HUNGER_TICKS);
+        update_touch_controls_display(self.world.config().board == BoardType::Hex);
+        update_effects_display(&[]);
+        match self.world.time_limit() {
+            Some(limit) => update_time_left_display(limit),
+            None => update_duration_display(0),
+        }
     }
 
-    fn init_snake(&self) -> Vec<Point> {
-        let first_point = self.calc_center();
-        let mut snake = vec![first_point];
-        let mut i :i32 = 1;
-        while i < INITIAL_SNAKE_LEN.try_into().unwrap() {
-            let point = Point{x: first_point.x, y: first_point.y - i};
-            snake.push(point);
-            i = i + 1;
+    // an empty board of the given size with the title menu, before the first game
+    pub fn show_title(&mut self, columns: i32, rows: i32, settings: DisplaySettings, context: web_sys::CanvasRenderingContext2d) {
+        self.settings = settings;
+        self.context = Some(context);
+        self.world = World::new(WorldConfig { columns, rows, ..WorldConfig::default() });
+        self.world.quit();
+        self.menu = Some(Menu::title());
+        self.countdown = None;
+        self.minimap_walls = None;
+        self.relayout();
+    }
+
+    // leaves the running game for the title menu, without a result
+    pub fn quit(&mut self) {
+        self.world.quit();
+        self.audio.set_music(false);
+        self.animations = Animations::new(false, 0);
+        self.menu = Some(Menu::title());
+        self.countdown = None;
+        self.draw_frame();
+    }
+
+    // back into the paused game, after a countdown if there is one
+    pub fn resume(&mut self) {
+        self.menu = None;
+        self.countdown = self.new_countdown(Then::Resume);
+        if self.countdown.is_none() {
+            self.world.set_input(' ');
         }
-        snake
     }
 
-    pub fn is_over(&self) -> bool {
-        self.over
+    pub fn toggle_debug(&mut self) {
+        self.debug.toggle();
+        self.draw_frame();
     }
 
-    pub fn set_input(&mut self, input: char) {
-        self.input = input;
+    // puzzles wait for the first move anyway
+    fn new_countdown(&self, then: Then) -> Option<Countdown> {
+        if self.settings.countdown == 0 || self.world.game_mode() == GameMode::PUZZLE {
+            return None;
+        }
+        Some(Countdown::new(self.settings.countdown, then))
     }
 
-    pub fn set_pressed(&mut self, pressed: bool) {
-        self.pressed = pressed;
-        if self.game_mode == GameMode::LONG {
-            if pressed {
-                self.speed = 6;
-            } else {
-                self.speed = 1;
-            }
-            update_speed_display(self.speed);
+    // rewrites the title and pause menus after a change of language, summaries keep their language
+    pub fn translate_menu(&mut self) {
+        match self.menu.as_ref().map(Menu::screen) {
+            Some(Screen::Title) => self.menu = Some(Menu::title()),
+            Some(Screen::Pause) => self.menu = Some(Menu::pause()),
+            _ => (),
         }
+        self.draw_frame();
     }
 
-    pub fn world_loop_contents(&mut self, timestamp :u32) -> bool {
-        if self.timestamp_game_start == 0 {
-            self.timestamp_game_start = timestamp;
-        }
-        if !self.over && self.enough_time_passed(timestamp) {
-            self.process_input(timestamp);
-            if self.pause {
-                self.draw_pause();
-            } else {
-                let continue_rendering = self.update_world();
-                if continue_rendering {
-                    update_duration_display(self.calc_duration(timestamp));
-                    self.draw();
-                } else {
-                    self.game_over(timestamp);
-                    return false;
-                }
-            }
+    pub fn set_audio_settings(&mut self, settings: AudioSettings) {
+        self.audio.set_settings(settings);
+    }
+
+    pub fn has_menu(&self) -> bool {
+        self.menu.is_some()
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    // the item chosen, if any; what it does is up to the caller
+    pub fn menu_input(&mut self, input: MenuInput) -> Option<MenuItem> {
+        let item = self.menu.as_mut()?.input(input);
+        self.draw_frame();
+        item
+    }
+
+    pub fn menu_tap(&mut self, x: f64, y: f64) -> Option<MenuItem> {
+        let (width, height) = (self.width as f64, self.height as f64);
+        let item = self.menu.as_mut()?.tap(x, y, width, height);
+        self.draw_frame();
+        item
+    }
+
+    // fits the canvas to the viewport and draws the game again, as resizing clears the canvas
+    pub fn relayout(&mut self) {
+        let context = match &self.context {
+            Some(context) => context,
+            None => return,
+        };
+        let canvas = match context.canvas() {
+            Some(canvas) => canvas,
+            None => return,
+        };
+        let config = self.world.config();
+        let layout = layout::fit(config.columns, config.rows, config.board, self.settings.max_block_size);
+        layout::apply(&canvas, context, &layout);
+        self.width = layout.width;
+        self.height = layout.height;
+        self.board_width = layout.board_width;
+        self.board_height = layout.board_height;
+        self.block_size = layout.block_size;
+        if layout.is_scrolling() && self.minimap_walls.is_none() {
+            self.minimap_walls = self.render_minimap_walls();
         }
-        true
+        self.follow_head();
+        self.camera.jump();
+        self.draw_frame();
     }
 
-    fn enough_time_passed(&mut self, timestamp :u32) -> bool {
-        if timestamp > self.timestamp_last_frame {
-            if (timestamp - self.timestamp_last_frame) as i32 > self.frame_time_threshold() {
-                self.timestamp_last_frame = timestamp;
-                return true;
+    // the board with effects, and the text for the state of the game on top
+    fn draw_frame(&mut self) {
+        let context = match &self.context {
+            Some(context) => context.clone(),
+            None => return,
+        };
+        let title = self.menu.as_ref().is_some_and(|menu| menu.screen() == Screen::Title);
+        self.draw_clear(&context);
+        if !title {
+            // from board to canvas coordinates
+            let (dx, dy) = self.animations.shake_offset();
+            context.save();
+            crate::utils::handle_js_error(context.translate(dx - self.camera.x, dy - self.camera.y));
+            self.draw();
+            if self.debug.shows_coordinates() {
+                self.draw_cell_coordinates(&context);
+            }
+            self.animations.draw(&context);
+            context.restore();
+            if self.is_scrolling() {
+                self.draw_minimap(&context);
+            }
+        }
+        if let Some(countdown) = &self.countdown {
+            countdown.draw(&context, self.width as f64, self.height as f64);
+        }
+        // the summary waits for the death animation
+        if let Some(menu) = &self.menu {
+            if !self.animations.is_dying() {
+                menu.draw(&context, self.width as f64, self.height as f64);
             }
         }
-        false
+        if self.debug.is_visible() {
+            self.debug.draw(&context, &self.debug_lines());
+        }
     }
 
-    fn frame_time_threshold(&self) -> i32 {
-        let speed_increase = if self.touch_mode {SPEED_INCREASE_MS_MODE_TOUCH} else {SPEED_INCREASE_MS_MODE_KEYBOARD};
-        FRAME_RATE_SPEED_1 - self.speed * speed_increase
+    // a finished game still counts as running while its animations play
+    pub fn is_over(&self) -> bool {
+        self.world.is_over() && !self.animations.is_active()
+    }
+
+    pub fn set_input(&mut self, input: char) {
+        self.world.set_input(input);
+    }
+
+    pub fn set_pressed(&mut self, pressed: bool) {
+        let speed_before = self.world.speed();
+        self.world.set_pressed(pressed);
+        if self.world.game_mode() == GameMode::LONG {
+            update_speed_display(self.world.speed());
+            if self.world.speed() != speed_before {
+                self.audio.set_speed(self.world.speed());
+                announce(&i18n::format("announce.speed", &[&self.world.speed().to_string()]));
+            }
+        }
     }
 
-    fn process_input(&mut self, timestamp :u32) {
-        // don't allow opposite direction
-        match &self.input {
-            'a' => {
-                if self.direction != Direction::RIGHT {
-                    self.direction = Direction::LEFT
+    pub fn world_loop_contents(&mut self, timestamp :u32) -> bool {
+        let mut redraw = true;
+        self.timestamp_frame = timestamp;
+        self.debug.on_frame(timestamp);
+        if self.update_countdown(timestamp) {
+            self.looping = true;
+            return self.looping;
+        }
+        let paused_before = self.world.is_paused();
+        let snake_before = if self.settings.smooth {self.world.snake().to_vec()} else {Vec::new()};
+        let direction_before = self.world.direction_input();
+        // puzzles only tick on input, so their ticks are never late
+        let threshold = (self.world.game_mode() != GameMode::PUZZLE).then(|| self.world.frame_time_threshold().max(0) as u32);
+        let tick = self.world.tick(timestamp);
+        if !matches!(tick, Tick::Waiting) {
+            self.debug.on_tick(timestamp, threshold);
+        }
+        match tick {
+            Tick::Waiting => redraw = false,
+            Tick::Paused => {
+                // the pause menu stays as it is until there is input for it
+                redraw = self.menu.is_none();
+                if redraw {
+                    self.menu = Some(Menu::pause());
+                    announce(&i18n::text("announce.paused"));
+                    self.audio.play(Effect::Pause);
                 }
+                self.achievements.on_pause();
+                self.last_weighted_score = self.world.weighted_score();
+                update_text_display(self.world.score(), self.world.weighted_score(), self.world.speed() as u32);
             },
-            's' => {
-                if self.direction != Direction::UP {
-                    self.direction = Direction::DOWN
+            Tick::Moved(events) => {
+                self.menu = None;
+                if paused_before {
+                    announce(&i18n::text("announce.resumed"));
+                    self.audio.play(Effect::Pause);
                 }
-            },
-            'd' => {
-                if self.direction != Direction::LEFT {
-                    self.direction = Direction::RIGHT
+                if self.world.direction_input() != direction_before {
+                    self.audio.play(Effect::Turn);
                 }
-            },
-            'w' => {
-                if self.direction != Direction::DOWN {
-                    self.direction = Direction::UP
+                self.previous_snake = snake_before;
+                self.timestamp_last_move = timestamp;
+                if events.food_eaten {
+                    self.handle_food_eaten(&events);
                 }
+                if events.tail_cut {
+                    self.shake();
+                }
+                let duration = self.world.calc_duration(timestamp);
+                match self.world.time_limit() {
+                    Some(limit) => update_time_left_display(limit.saturating_sub(duration)),
+                    None => update_duration_display(duration),
+                }
+                update_goal_display(self.goal_text().as_deref());
+                update_hunger_display(self.world.hunger(), HUNGER_TICKS);
+                self.update_effects();
+                self.update_opponents();
+                update_puzzle_help_display("");
             },
-            ' ' => {
-                self.toggle_pause(timestamp);
-            }
-            _ => (),
+            Tick::Over => self.game_over(),
+            Tick::LevelComplete => self.level_complete(),
         }
-    }
-
-    fn toggle_pause(&mut self, timestamp :u32) {
-        log!("toggling pause");
-        if !self.pause {
-            self.timestamp_pause_start = timestamp;
-        } else {
-            self.pause_duration_sum += timestamp - self.timestamp_pause_start;
-            self.timestamp_pause_start = 0;
+        // effects move on every frame, not only on ticks
+        self.animations.update(timestamp);
+        self.follow_head();
+        self.camera.update(timestamp, !self.settings.reduced_motion);
+        self.audio.set_music(!self.world.is_over() && !self.world.is_paused());
+        let moving = self.settings.smooth && !self.world.is_over() && !self.world.is_paused();
+        if redraw || moving || self.camera.is_moving() || self.animations.is_active() || self.debug.is_visible() {
+            self.draw_frame();
         }
-        self.pause = !self.pause;
-        self.input = DEFAULT_INPUT;
+        self.looping = !self.is_over();
+        self.looping
     }
 
-    fn update_world(&mut self) -> bool {
-        self.move_snake();
-        self.handle_food_collision();
-        if self.check_border_collision() {
+    // a frame of the countdown, the world doesn't tick while it runs; false once it is over
+    fn update_countdown(&mut self, timestamp: u32) -> bool {
+        let countdown = match self.countdown.as_mut() {
+            Some(countdown) => countdown,
+            None => return false,
+        };
+        // the first number is announced as well
+        let number_before = if countdown.is_started() {countdown.number()} else {0};
+        let running = countdown.update(timestamp);
+        let number = countdown.number();
+        if !running {
+            if countdown.then() == Then::Resume {
+                self.world.set_input(' ');
+            }
+            self.countdown = None;
+            self.debug.skip_timing();
             return false;
         }
-        if self.check_snake_collision() {
-            return false;
+        if number != number_before {
+            announce(&number.to_string());
+            self.audio.play(Effect::Countdown);
         }
+        self.draw_frame();
         true
     }
 
-    fn move_snake(&mut self) {
-        let mut i = 0;
-        let mut prev = self.snake[0];
-        for point in &mut self.snake {
-            if i == 0 {
-                match &self.direction {
-                    Direction::UP => {
-                        point.y -= 1;
-                    },
-                    Direction::DOWN => {
-                        point.y += 1;
-                    },
-                    Direction::LEFT => {
-                        point.x -= 1;
-                    },
-                    Direction::RIGHT => {
-                        point.x += 1;
-                    },
-                }
-            } else {
-                let tmp = prev;
-                prev = *point;
-                *point = tmp;
-            }
-            i = i + 1;
-        }
-    }
-
-    fn handle_food_collision(&mut self) {
-        let point = &self.snake[0];
-        let touch_mode_collision = if self.touch_mode {
-            let relevant_points = vec![
-                Point{x: self.snake[0].x - 1, y: self.snake[0].y - 1},
-                Point{x: self.snake[0].x - 1, y: self.snake[0].y},
-                Point{x: self.snake[0].x    , y: self.snake[0].y - 1},
-                Point{x: self.snake[0].x + 1, y: self.snake[0].y},
-                Point{x: self.snake[0].x    , y: self.snake[0].y + 1},
-                Point{x: self.snake[0].x + 1, y: self.snake[0].y + 1},
-            ];
-            relevant_points.contains(&self.food)
-        } else {
-            false
-        };
-        if point == &self.food || touch_mode_collision {
-            let last_point = self.snake[self.snake.len() - 1];
-            self.snake.push(last_point);
-            self.score = self.score + 1;
-            self.inc_colour_index();
-            if self.game_mode == GameMode::FAST {
-                if self.score % SPEED_INCREASE_AT_SCORE == 0 {
-                    self.speed = self.speed + 1;
-                    log!("frame_time_threshold: {}", self.frame_time_threshold());
-                    if self.speed >= SPEED_TO_SET_BG_COL {
-                        let bg_col_idx = (self.speed - SPEED_TO_SET_BG_COL) as usize % BACKGROUND_COLOURS.len();
-                        set_background_colour(BACKGROUND_COLOURS[bg_col_idx]);
-                    }
-                }
-            }
-            update_text_display(self.score, self.speed as u32);
-            self.place_food();
+    // timing from the overlay, then the state of the world
+    fn debug_lines(&self) -> Vec<String> {
+        let board = self.world.config().board;
+        let direction = self.world.direction_input();
+        let mut lines = self.debug.timing_lines();
+        lines.push(format!("speed: {}, tick every {} ms", self.world.speed(), self.world.frame_time_threshold()));
+        lines.push(format!("length: {}", self.world.snake().len()));
+        if let Some(head) = self.world.snake().first() {
+            lines.push(format!("head: {},{}", head.x, head.y));
         }
+        lines.push(format!("direction: {} ({:?})", puzzle::input_name(board, direction), direction));
+        let input = self.world.input();
+        lines.push(if input == DEFAULT_INPUT {"queued input: -".to_string()} else {format!("queued input: {:?}", input)});
+        lines
     }
 
-    fn place_food(&mut self) {
-        let mut point = Point{x: 0, y: 0};
+    fn draw_cell_coordinates(&self, context: &web_sys::CanvasRenderingContext2d) {
+        if self.block_size < debug::MIN_COORDINATES_BLOCK_SIZE {
+            return;
+        }
+        let (from, to) = self.visible_cells();
+        let cells: Vec<(i32, i32, (f64, f64))> = (from.y..=to.y)
+            .flat_map(|y| (from.x..=to.x).map(move |x| (x, y)))
+            .map(|(x, y)| (x, y, self.cell_center(&Point{x, y})))
+            .collect();
+        self.debug.draw_coordinates(context, &cells, self.block_size);
+    }
+
+    fn update_effects(&self) {
+        // remaining ticks at the current speed
+        let tick_ms = self.world.frame_time_threshold().max(0) as u32;
+        let effects: Vec<(String, u32)> = self.world.effects().effects().iter()
+            .map(|effect| (i18n::text(&format!("power-up.{}", powerups::spec(effect.kind).id)), effect.ticks_left * tick_ms))
+            .collect();
+        update_effects_display(&effects);
+    }
 
-        let mut food_x_min :u32 = 0;
-        let mut food_y_min :u32 = 0;
-        let mut food_x_max :i32 = self.calc_point_compontent(self.width);
-        let mut food_y_max :i32 = self.calc_point_compontent(self.height);
+    fn update_opponents(&self) {
+        let opponents: Vec<(&str, u32, bool)> = self.world.opponents().iter().enumerate()
+            .map(|(i, opponent)| (self.opponent_colour(i), opponent.score, opponent.alive))
+            .collect();
+        update_opponents_display(&opponents);
+    }
 
-        if self.touch_mode {
-            food_x_min = TOUCH_MODE_FOOD_BORDER_OFFSET;
-            food_y_min = TOUCH_MODE_FOOD_BORDER_OFFSET;
-            food_x_max = food_x_max - TOUCH_MODE_FOOD_BORDER_OFFSET as i32;
-            food_y_max = food_y_max - TOUCH_MODE_FOOD_BORDER_OFFSET as i32;
+    // progress towards the goal of a campaign level or puzzle, or the target length in versus games
+    fn goal_text(&self) -> Option<String> {
+        if self.world.game_mode() == GameMode::VERSUS {
+            return Some(format!("{}/{}", self.world.snake().len(), VERSUS_TARGET_LENGTH));
+        }
+        let target = self.world.food_target()?;
+        let mut text = format!("{}/{}", self.world.score(), target);
+        if let Some(moves) = self.world.moves_left() {
+            text += &i18n::format("goal.moves-left", &[&moves.to_string()]);
         }
+        if let Some(turns) = self.world.turns_left() {
+            text += &i18n::format("goal.turns-left", &[&turns.to_string()]);
+        }
+        Some(text)
+    }
 
-        loop {
-            point.x = random(food_x_min, food_x_max);
-            point.y = random(food_y_min, food_y_max);
+    // next move or all moves solving the puzzle from where the snake is
+    pub fn show_puzzle_help(&self, full: bool) {
+        if self.world.game_mode() != GameMode::PUZZLE || self.world.is_over() {
+            return;
+        }
+        let board = self.world.config().board;
+        // directions on square boards, keys on hex boards
+        let input_name = |input: char| match board {
+            BoardType::Square => i18n::text(&format!("direction.{}", puzzle::input_name(board, input))),
+            BoardType::Hex => puzzle::input_name(board, input).to_string(),
+        };
+        let help = match puzzle::solve(&self.world, puzzle::MAX_HINT_STATES) {
+            Some(moves) if full => {
+                let names: Vec<String> = moves.iter().map(|input| input_name(*input)).collect();
+                i18n::format("puzzle.solution", &[&names.join(" ")])
+            },
+            Some(moves) => i18n::format("puzzle.hint", &[&input_name(moves[0])]),
+            None => i18n::text("puzzle.no-solution"),
+        };
+        update_puzzle_help_display(&help);
+    }
 
-            if !self.snake.contains(&point) {
-                break;
+    fn handle_food_eaten(&mut self, events: &TickEvents) {
+        let (x, y) = self.cell_center(&self.world.snake()[0]);
+        self.animations.burst(x, y, self.food_colour());
+        let weighted_score = self.world.weighted_score();
+        self.animations.popup(x, y, &format!("+{}", weighted_score.saturating_sub(self.last_weighted_score)));
+        self.last_weighted_score = weighted_score;
+        self.inc_colour_index();
+        let speed = self.world.speed();
+        self.audio.set_speed(speed);
+        self.audio.play(if events.speed_up {Effect::SpeedUp} else {Effect::Eat});
+        if events.speed_up {
+            log!("frame_time_threshold: {}", self.world.frame_time_threshold());
+            if speed >= SPEED_TO_SET_BG_COL && !self.settings.reduced_motion {
+                let bg_col_idx = (speed - SPEED_TO_SET_BG_COL) as usize % BACKGROUND_COLOURS.len();
+                set_background_colour(BACKGROUND_COLOURS[bg_col_idx]);
             }
         }
-        log!("placing food at: {},{}", point.x, point.y);
-        self.food = point;
+        update_text_display(self.world.score(), self.world.weighted_score(), speed as u32);
+        if events.speed_up {
+            announce(&i18n::format("announce.score-speed", &[&self.world.score().to_string(), &speed.to_string()]));
+        } else {
+            announce(&i18n::format("announce.score", &[&self.world.score().to_string()]));
+        }
+        self.achievements.on_food_eaten(&self.world);
+        let food = self.world.food();
+        log!("placing food at: {},{}", food.x, food.y);
     }
 
     fn inc_colour_index(&mut self) {
-        if self.colour_index == COLOURS.len() -1 {
+        if self.colour_index >= self.settings.palette.food().len() -1 {
             self.colour_index = 0;
         } else {
             self.colour_index = self.colour_index + 1;
         }
     }
 
-    fn check_border_collision(&self) -> bool {
-        let point = &self.snake[0];
-        match &self.direction {
-            Direction::UP => {
-                if point.y < 0 {
-                    return true;
-                }
-            },
-            Direction::DOWN => {
-                if point.y >= self.calc_point_compontent(self.height) {
-                    return true;
-                }
-            },
-            Direction::LEFT => {
-                if point.x < 0 {
-                    return true;
-                }
-            },
-            Direction::RIGHT => {
-                if point.x > self.calc_point_compontent(self.width) {
-                    return true;
-                }
-            },
+    fn game_over(&mut self) {
+        log!("game over");
+        if self.world.death_cause().is_some() && !self.world.is_won() {
+            let segments = self.world.snake().iter().enumerate()
+                .map(|(i, point)| (self.cell_center(point), self.segment_colour(i)))
+                .collect();
+            self.animations.start_death(segments);
         }
-        return false;
+        self.audio.play(if self.world.is_won() {Effect::SpeedUp} else {Effect::Death});
+        // a crash shakes the board
+        if let Some(DeathCause::Border | DeathCause::Wall | DeathCause::Snake | DeathCause::Hazard | DeathCause::Opponent) = self.world.death_cause() {
+            self.shake();
+        }
+        let input_mode = if self.world.config().touch_mode {"touch"} else {"keyboard"};
+        let game_mode = self.world.config().mode_id();
+        let mut rank = None;
+        if self.world.game_mode() != GameMode::CAMPAIGN && self.world.game_mode() != GameMode::PUZZLE {
+            let latest_timestamp = highscore::add_score(&self.name, input_mode, &self.world);
+            rank = latest_timestamp.as_deref().and_then(highscore::rank);
+            highscore::print_highscores(latest_timestamp);
+        }
+        self.record_game(&game_mode);
+        let menu = self.game_over_menu(rank);
+        announce(&menu.text());
+        self.menu = Some(menu);
     }
 
-    fn check_snake_collision(&self) -> bool {
-        let first_point = self.snake[0];
-        let rest_of_snake = &self.snake[1..self.snake.len()];
-        return rest_of_snake.contains(&first_point);
+    fn level_complete(&mut self) {
+        log!("level complete");
+        self.audio.play(Effect::SpeedUp);
+        if let Some(level) = self.world.config().level {
+            campaign::complete_level(level);
+        }
+        self.record_game(&self.world.config().mode_id());
+        let menu = self.level_complete_menu();
+        announce(&menu.text());
+        self.menu = Some(menu);
     }
 
-    fn game_over(&mut self, timestamp :u32) {
-        log!("game over");
-        self.over = true;
-        self.draw_game_over();
-        let input_mode = if self.touch_mode {"Touch"} else {"Keyboard"};
-        let game_mode = if self.game_mode == GameMode::FAST {"Fast Snake"} else {"Long Snake"};
-        let duration = self.calc_duration(timestamp);
-        let latest_timestamp = highscore::add_score(&self.name, self.score, input_mode, game_mode, duration);
-        highscore::print_highscores(latest_timestamp);
+    fn record_game(&mut self, game_mode: &str) {
+        profile::record_game(&self.name, self.world.score(), self.world.snake().len() as u32, game_mode, self.world.duration());
+        profile::print_profiles();
+        self.achievements.on_game_over(&self.world);
+        achievements::print_achievements();
     }
 
-    fn calc_duration(&self, timestamp :u32) -> u32 {
-        timestamp - self.timestamp_game_start - self.pause_duration_sum
+    fn shake(&mut self) {
+        if !self.settings.reduced_motion {
+            self.animations.shake();
+        }
     }
 
-    fn calc_center(&self) -> Point {
-        Point {
-            x: (self.width / self.block_size / 2) as i32,
-            y: (self.height / self.block_size / 2) as i32,
+    fn food_colour(&self) -> &'static str {
+        let colours = self.settings.palette.food();
+        colours[self.colour_index % colours.len()]
+    }
+
+    fn opponent_colour(&self, i: usize) -> &'static str {
+        let colours = self.settings.palette.opponents();
+        colours[i % colours.len()]
+    }
+
+    // the first segments are black, the rest cycle through the colours of the food eaten
+    fn segment_colour(&self, i: usize) -> &'static str {
+        let colours = self.settings.palette.food();
+        if i < INITIAL_SNAKE_LEN {
+            "#000"
+        } else {
+            colours[(i - INITIAL_SNAKE_LEN) % colours.len()]
         }
     }
+
+    fn is_scrolling(&self) -> bool {
+        self.board_width > self.width || self.board_height > self.height
+    }
+
+    fn follow_head(&mut self) {
+        let focus = match self.world.snake().first() {
+            Some(head) => self.cell_center(head),
+            None => return,
+        };
+        let view = (self.width as f64, self.height as f64);
+        let board = (self.board_width as f64, self.board_height as f64);
+        self.camera.follow(focus, view, board);
+    }
+
+    // first and last column and row in the view, one more on each side for cells partly in it
+    fn visible_cells(&self) -> (Point, Point) {
+        let config = self.world.config();
+        let block_size = self.block_size as f64;
+        let (left, top) = (self.camera.x, self.camera.y);
+        let (right, bottom) = (left + self.width as f64, top + self.height as f64);
+        let (from, to) = match config.board {
+            BoardType::Square => (
+                Point{x: (left / block_size).floor() as i32, y: (top / block_size).floor() as i32},
+                Point{x: (right / block_size).floor() as i32, y: (bottom / block_size).floor() as i32},
+            ),
+            BoardType::Hex => (
+                hex::approximate_cell(left, top, block_size),
+                hex::approximate_cell(right, bottom, block_size),
+            ),
+        };
+        (
+            Point{x: (from.x - 1).max(0), y: (from.y - 1).max(0)},
+            Point{x: (to.x + 1).min(config.columns - 1), y: (to.y + 1).min(config.rows - 1)},
+        )
+    }
+
+    fn is_visible(&self, point: &Point) -> bool {
+        if !self.is_scrolling() {
+            return true;
+        }
+        let (from, to) = self.visible_cells();
+        point.x >= from.x && point.x <= to.x && point.y >= from.y && point.y <= to.y
+    }
+
+    fn cell_center(&self, point: &Point) -> (f64, f64) {
+        let coord = self.calc_coord(point);
+        let block_size_half = self.block_size as f64 / 2.0;
+        (coord.x as f64 + block_size_half, coord.y as f64 + block_size_half)
+    }
+
+    // top left corner of a block_size square centred on the cell
     fn calc_coord(&self, point: &Point) -> Point {
+        if self.world.config().board == BoardType::Hex {
+            let (x, y) = hex::center(*point, self.block_size as f64);
+            let block_size_half = self.block_size as f64 / 2.0;
+            return Point {
+                x: (x - block_size_half).round() as i32,
+                y: (y - block_size_half).round() as i32,
+            };
+        }
         Point {
             x: point.x * self.block_size as i32,
             y: point.y * self.block_size as i32,
         }
     }
-    fn calc_point_compontent(&self, component: u32) -> i32 {
-        (component / self.block_size).try_into().unwrap()
-    }
 
     fn draw(&self) {
-        if self.context.is_some() {
-            let context: &web_sys::CanvasRenderingContext2d = self.context.as_ref().unwrap();
-            self.draw_clear(context);
-            if self.draw_grid {
+        if let Some(context) = &self.context {
+            if self.settings.draw_grid {
                 self.draw_coord_sys(context);
             }
+            self.draw_walls(context);
+            self.draw_portals(context);
             self.draw_snake(context);
+            self.draw_opponents(context);
+            self.draw_hazards(context);
+            self.draw_power_up(context);
             self.draw_food(context);
         }
     }
@@ -469,73 +746,361 @@ impl Game {
         context.fill_rect(0.0, 0.0, self.width.into(), self.height.into());
     }
     fn draw_coord_sys(&self, context: &web_sys::CanvasRenderingContext2d) {
-        let mut i = 0;
         context.set_stroke_style(&JsValue::from_str("#999"));
         context.set_line_width(1.0);
-        while i < self.width {
+        let (from, to) = self.visible_cells();
+        if self.world.config().board == BoardType::Hex {
+            for y in from.y..=to.y {
+                for x in from.x..=to.x {
+                    self.trace_hexagon(&Point{x, y}, context);
+                    context.stroke();
+                }
+            }
+            return;
+        }
+        let block_size = self.block_size as f64;
+        let (left, right) = (from.x as f64 * block_size, (to.x + 1) as f64 * block_size);
+        let (top, bottom) = (from.y as f64 * block_size, (to.y + 1) as f64 * block_size);
+        for x in from.x..=to.x {
             context.begin_path();
-            context.move_to(i.into(), 0.into());
-            context.line_to(i.into(), self.height.into());
+            context.move_to(x as f64 * block_size, top);
+            context.line_to(x as f64 * block_size, bottom);
             context.close_path();
             context.stroke();
-            i += self.block_size;
         }
-        i = 0;
-        while i < self.height {
+        for y in from.y..=to.y {
             context.begin_path();
-            context.move_to(0.into(), i.into());
-            context.line_to(self.width.into(), i.into());
+            context.move_to(left, y as f64 * block_size);
+            context.line_to(right, y as f64 * block_size);
             context.close_path();
             context.stroke();
-            i += self.block_size;
         }
     }
+    fn draw_walls(&self, context: &web_sys::CanvasRenderingContext2d) {
+        let (from, to) = self.visible_cells();
+        for y in from.y..=to.y {
+            for x in from.x..=to.x {
+                let point = Point{x, y};
+                if self.world.is_wall(point) {
+                    self.draw_point(&point, WALL_COLOUR, context);
+                }
+            }
+        }
+    }
+    fn draw_portals(&self, context: &web_sys::CanvasRenderingContext2d) {
+        let block_size_half = self.block_size as f64 / 2.0;
+        context.set_line_width(2.0);
+        for (i, (a, b)) in self.world.portals().iter().enumerate() {
+            context.set_stroke_style_str(PORTAL_COLOURS[i % PORTAL_COLOURS.len()]);
+            for end in [a, b] {
+                let coord = self.calc_coord(end);
+                context.begin_path();
+                context
+                    .ellipse(coord.x as f64 + block_size_half, coord.y as f64 + block_size_half,
+                        block_size_half * 0.6, block_size_half - 1.0, 0.0, 0.0, std::f64::consts::PI * 2.0)
+                    .unwrap();
+                context.stroke();
+            }
+        }
+    }
+    fn draw_hazards(&self, context: &web_sys::CanvasRenderingContext2d) {
+        let block_size = self.block_size as f64;
+        for hazard in self.world.hazards() {
+            let coord = self.calc_coord(&hazard.position);
+            let (x, y) = (coord.x as f64, coord.y as f64);
+            context.begin_path();
+            match hazard.kind {
+                HazardKind::Patrol => {
+                    context.set_fill_style_str(HAZARD_COLOUR_PATROL);
+                    context.rect(x + 1.0, y + 1.0, block_size - 2.0, block_size - 2.0);
+                },
+                HazardKind::Bouncer => {
+                    context.set_fill_style_str(HAZARD_COLOUR_BOUNCER);
+                    context
+                        .arc(x + block_size / 2.0, y + block_size / 2.0, block_size / 2.0, 0.0, std::f64::consts::PI * 2.0)
+                        .unwrap();
+                },
+                HazardKind::Hunter => {
+                    context.set_fill_style_str(HAZARD_COLOUR_HUNTER);
+                    context.move_to(x + block_size / 2.0, y);
+                    context.line_to(x + block_size, y + block_size);
+                    context.line_to(x, y + block_size);
+                    context.close_path();
+                },
+            }
+            context.fill();
+        }
+    }
+    fn draw_power_up(&self, context: &web_sys::CanvasRenderingContext2d) {
+        let (kind, point) = match self.world.power_up() {
+            Some(power_up) => power_up,
+            None => return,
+        };
+        let (colour, letter) = match kind {
+            PowerUpKind::Ghost => ("#AAA", "G"),
+            PowerUpKind::Invincibility => ("#FC0", "I"),
+            PowerUpKind::SlowMotion => ("#0CF", "S"),
+            PowerUpKind::Magnet => ("#F0C", "M"),
+        };
+        let coord = self.calc_coord(&point);
+        let block_size = self.block_size as f64;
+        context.set_fill_style_str(colour);
+        context.fill_rect(coord.x.into(), coord.y.into(), block_size, block_size);
+        context.set_fill_style_str("#000");
+        context.set_font(&format!("bold {}px sans-serif", self.block_size));
+        context.set_text_align("center");
+        context.set_text_baseline("middle");
+        let result = context.fill_text(letter, coord.x as f64 + block_size / 2.0, coord.y as f64 + block_size / 2.0);
+        crate::utils::handle_js_error(result);
+        context.set_text_baseline("alphabetic");
+    }
     fn draw_snake(&self, context: &web_sys::CanvasRenderingContext2d) {
-        let mut i :usize = 0;
-        for point in &self.snake {
-            let colour = if i < INITIAL_SNAKE_LEN {
-                "#000"
-            } else {
-                COLOURS[(i - INITIAL_SNAKE_LEN) % COLOURS.len()]
-            };
-            self.draw_point(point, colour, context);
-            i = i + 1;
+        // a ghost snake is see-through
+        if self.world.effects().is_active(PowerUpKind::Ghost) {
+            context.set_global_alpha(0.5);
+        }
+        if self.settings.smooth && !self.world.is_over() && !self.world.is_paused() {
+            self.draw_snake_smooth(context);
+            context.set_global_alpha(1.0);
+            return;
+        }
+        // segments already gone in the death animation
+        let hidden = self.animations.hidden_segments();
+        for (i, point) in self.world.snake().iter().enumerate().skip(hidden) {
+            if !self.is_visible(point) {
+                continue;
+            }
+            self.draw_point(point, self.segment_colour(i), context);
+        }
+        context.set_global_alpha(1.0);
+    }
+    // The head slides into its new cell and the tail out of its old one, by the share of the tick passed.
+    // Segments are joined by round lines, which rounds the corners where the snake turned.
+    fn draw_snake_smooth(&self, context: &web_sys::CanvasRenderingContext2d) {
+        let snake = self.world.snake();
+        let tick_time = self.world.frame_time_threshold().max(1) as f64;
+        let fraction = (self.timestamp_frame.saturating_sub(self.timestamp_last_move) as f64 / tick_time).min(1.0);
+        let mut centres: Vec<(f64, f64)> = snake.iter().map(|point| self.cell_center(point)).collect();
+        if snake.len() > 1 && self.is_next_to(centres[1], centres[0]) {
+            centres[0] = lerp(centres[1], centres[0], fraction);
+        }
+        // a snake that grew keeps its tail where it was
+        if let (Some(old_tail), Some(tail)) = (self.previous_snake.last(), centres.last().copied()) {
+            let old_tail = self.cell_center(old_tail);
+            if self.previous_snake.len() == snake.len() && self.is_next_to(old_tail, tail) {
+                centres.push(lerp(old_tail, tail, fraction));
+            }
+        }
+        context.set_line_width(self.block_size as f64);
+        context.set_line_cap("round");
+        context.set_line_join("round");
+        for (i, pair) in centres.windows(2).enumerate() {
+            if !self.is_visible(&snake[i.min(snake.len() - 1)]) {
+                continue;
+            }
+            // no line through portals or around the border
+            let (from, to) = if self.is_next_to(pair[0], pair[1]) {(pair[0], pair[1])} else {(pair[0], pair[0])};
+            context.set_stroke_style_str(self.segment_colour(i));
+            context.begin_path();
+            context.move_to(from.0, from.1);
+            context.line_to(to.0, to.1);
+            context.stroke();
+        }
+        if centres.len() == 1 {
+            self.draw_point(&snake[0], self.segment_colour(0), context);
+        }
+        context.set_line_cap("butt");
+        context.set_line_join("miter");
+    }
+
+    fn is_next_to(&self, a: (f64, f64), b: (f64, f64)) -> bool {
+        let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+        (dx * dx + dy * dy).sqrt() <= self.block_size as f64 * 1.5
+    }
+
+    fn draw_opponents(&self, context: &web_sys::CanvasRenderingContext2d) {
+        for (i, opponent) in self.world.opponents().iter().enumerate() {
+            let colour = self.opponent_colour(i);
+            for point in opponent.body.iter().filter(|point| self.is_visible(point)) {
+                self.draw_point(point, colour, context);
+            }
         }
     }
     fn draw_point(&self, point: &Point, colour: &str, context: &web_sys::CanvasRenderingContext2d) {
-        let coord = self.calc_coord(&point);
         context.set_fill_style(&JsValue::from_str(colour));
+        if self.world.config().board == BoardType::Hex {
+            self.trace_hexagon(point, context);
+            context.fill();
+            return;
+        }
+        let coord = self.calc_coord(&point);
         context.fill_rect(coord.x.into(), coord.y.into(), self.block_size.into(), self.block_size.into());
     }
+    fn trace_hexagon(&self, point: &Point, context: &web_sys::CanvasRenderingContext2d) {
+        let corners = hex::corners(*point, self.block_size as f64);
+        context.begin_path();
+        context.move_to(corners[0].0, corners[0].1);
+        for (x, y) in &corners[1..] {
+            context.line_to(*x, *y);
+        }
+        context.close_path();
+    }
     fn draw_food(&self, context: &web_sys::CanvasRenderingContext2d) {
-        context.set_stroke_style(&JsValue::from_str(COLOURS[self.colour_index]));
+        context.set_stroke_style_str(self.food_colour());
         context.set_line_width(3.0);
         let block_size_half:i32 = (self.block_size / 2) as i32;
-        let mut coord = self.calc_coord(&self.food);
+        let mut coord = self.calc_coord(&self.world.food());
         coord.x = coord.x + block_size_half;
         coord.y = coord.y + block_size_half;
+        let shape = if self.settings.food_shapes {FOOD_SHAPES[self.colour_index % FOOD_SHAPES.len()]} else {FoodShape::Circle};
+        let (x, y, radius) = (coord.x as f64, coord.y as f64, block_size_half as f64);
         context.begin_path();
-        context
-            .arc(coord.x.into(), coord.y.into(), block_size_half.into(), 0.0, std::f64::consts::PI * 2.0)
-            .unwrap();
+        match shape {
+            FoodShape::Circle => context
+                .arc(x, y, radius, 0.0, std::f64::consts::PI * 2.0)
+                .unwrap(),
+            FoodShape::Square => context.rect(x - radius * 0.8, y - radius * 0.8, radius * 1.6, radius * 1.6),
+            FoodShape::Triangle => {
+                context.move_to(x, y - radius);
+                context.line_to(x + radius, y + radius * 0.8);
+                context.line_to(x - radius, y + radius * 0.8);
+                context.close_path();
+            },
+            FoodShape::Diamond => {
+                context.move_to(x, y - radius);
+                context.line_to(x + radius, y);
+                context.line_to(x, y + radius);
+                context.line_to(x - radius, y);
+                context.close_path();
+            },
+        }
         context.stroke();
 
     }
-    fn draw_pause(&self) {
-        let context: &web_sys::CanvasRenderingContext2d = self.context.as_ref().unwrap();
-        context.set_font("bold 30px serif");
-        context.set_text_align("center");
-        context.set_fill_style(&JsValue::from_str("#00F"));
-        let result = context.fill_text("PAUSE", (self.width / 2).into(), (self.height / 2).into());
-        crate::utils::handle_js_error(result);
+    fn render_minimap_walls(&self) -> Option<web_sys::HtmlCanvasElement> {
+        let config = self.world.config();
+        let document = web_sys::window().unwrap().document().unwrap();
+        let canvas = document.create_element("canvas").ok()?.dyn_into::<web_sys::HtmlCanvasElement>().ok()?;
+        canvas.set_width(config.columns as u32);
+        canvas.set_height(config.rows as u32);
+        let context = canvas.get_context("2d").ok()??.dyn_into::<web_sys::CanvasRenderingContext2d>().ok()?;
+        context.set_fill_style_str(WALL_COLOUR);
+        for y in 0..config.rows {
+            for x in 0..config.columns {
+                if self.world.is_wall(Point{x, y}) {
+                    context.fill_rect(x as f64, y as f64, 1.0, 1.0);
+                }
+            }
+        }
+        Some(canvas)
     }
 
-    fn draw_game_over(&self) {
-        let context: &web_sys::CanvasRenderingContext2d = self.context.as_ref().unwrap();
-        context.set_font("bold 30px serif");
-        context.set_text_align("center");
-        context.set_fill_style(&JsValue::from_str("#F00"));
-        let result = context.fill_text("GAME OVER", (self.width / 2).into(), (self.height / 2).into());
-        crate::utils::handle_js_error(result);
+    // the whole board in the top right corner, with the snake, opponents, food and the part in view
+    fn draw_minimap(&self, context: &web_sys::CanvasRenderingContext2d) {
+        let config = self.world.config();
+        let (board_width, board_height) = (self.board_width as f64, self.board_height as f64);
+        let scale = (MINIMAP_SIZE / board_width.max(board_height))
+            .min(self.width as f64 * MINIMAP_SHARE / board_width)
+            .min(self.height as f64 * MINIMAP_SHARE / board_height);
+        let (width, height) = (board_width * scale, board_height * scale);
+        let (left, top) = (self.width as f64 - width - MINIMAP_MARGIN, MINIMAP_MARGIN);
+        // cells are laid out straight, also on hexagonal boards
+        let (cell_width, cell_height) = (width / config.columns as f64, height / config.rows as f64);
+        let dot = |point: &Point, size: f64| {
+            let (x, y) = (left + (point.x as f64 + 0.5) * cell_width, top + (point.y as f64 + 0.5) * cell_height);
+            context.fill_rect(x - size / 2.0, y - size / 2.0, size, size);
+        };
+
+        context.set_global_alpha(0.85);
+        context.set_fill_style_str("#FFF");
+        context.fill_rect(left, top, width, height);
+        if let Some(walls) = &self.minimap_walls {
+            context.set_image_smoothing_enabled(false);
+            let result = context.draw_image_with_html_canvas_element_and_dw_and_dh(walls, left, top, width, height);
+            crate::utils::handle_js_error(result);
+            context.set_image_smoothing_enabled(true);
+        }
+        context.set_global_alpha(1.0);
+        let size = cell_width.max(cell_height).max(2.0);
+        for (i, opponent) in self.world.opponents().iter().enumerate() {
+            context.set_fill_style_str(self.opponent_colour(i));
+            opponent.body.iter().for_each(|point| dot(point, size));
+        }
+        context.set_fill_style_str("#000");
+        self.world.snake().iter().for_each(|point| dot(point, size));
+        context.set_fill_style_str(self.food_colour());
+        dot(&self.world.food(), size * 2.0);
+
+        context.set_stroke_style_str("#000");
+        context.set_line_width(1.0);
+        context.stroke_rect(left, top, width, height);
+        context.set_stroke_style_str("#F00");
+        context.stroke_rect(left + self.camera.x * scale, top + self.camera.y * scale, self.width as f64 * scale, self.height as f64 * scale);
+    }
+
+    fn level_complete_menu(&self) -> Menu {
+        let (text, name) = match self.world.config().puzzle {
+            Some(index) => {
+                let name = puzzle::load_pack().puzzles.into_iter().nth(index).map_or(String::new(), |puzzle| puzzle.name);
+                ("summary.puzzle-solved", name)
+            },
+            None => {
+                let name = self.world.config().level.and_then(|level| campaign::LEVELS.get(level)).map_or(String::new(), |level| level.name());
+                ("summary.level-complete", name)
+            },
+        };
+        let lines = vec![name, i18n::format("summary.duration", &[&utils::format_duration(self.world.duration())])];
+        Menu::level_complete(&i18n::text(text), lines, self.next_level().is_some())
+    }
+
+    // index of the campaign level or puzzle after the one played, if there is one
+    fn next_level(&self) -> Option<usize> {
+        let config = self.world.config();
+        let (index, count) = match (config.level, config.puzzle) {
+            (Some(level), _) => (level, campaign::LEVELS.len()),
+            (None, Some(index)) => (index, puzzle::load_pack().puzzles.len()),
+            (None, None) => return None,
+        };
+        (index + 1 < count).then_some(index + 1)
+    }
+
+    // selects the campaign level or puzzle after the one played, for the next game
+    pub fn select_next_level(&self) {
+        match (self.world.config().level, self.next_level()) {
+            (Some(_), Some(level)) => campaign::print_levels(level),
+            (None, Some(index)) => puzzle::select_puzzle(index),
+            (_, None) => (),
+        }
+    }
+
+    fn game_over_menu(&self, rank: Option<u32>) -> Menu {
+        let (colour, text) = if self.world.is_won() {
+            ("#0A0", "summary.you-win")
+        } else if self.world.is_out_of_time() {
+            ("#F00", "summary.time-up")
+        } else {
+            ("#F00", "summary.game-over")
+        };
+        let cause = match self.world.death_cause() {
+            Some(DeathCause::Border) => "cause.border",
+            Some(DeathCause::Wall) => "cause.wall",
+            Some(DeathCause::Snake) => "cause.snake",
+            Some(DeathCause::Starvation) => "cause.starvation",
+            Some(DeathCause::Hazard) => "cause.hazard",
+            Some(DeathCause::Opponent) => "cause.opponent",
+            Some(DeathCause::Outgrown) => "cause.outgrown",
+            Some(DeathCause::OutOfMoves) => "cause.out-of-moves",
+            None => "",
+        };
+        let mut lines = Vec::new();
+        if !cause.is_empty() {
+            lines.push(i18n::text(cause));
+        }
+        lines.push(i18n::format("summary.score", &[&self.world.score().to_string(), &self.world.weighted_score().to_string()]));
+        lines.push(i18n::format("summary.duration", &[&utils::format_duration(self.world.duration())]));
+        if let Some(rank) = rank {
+            lines.push(i18n::format("summary.rank", &[&rank.to_string()]));
+        }
+        Menu::game_over(&i18n::text(text), colour, lines)
     }
 }
@@ -0,0 +1,89 @@
+// Gamepads in the standard mapping, polled as browsers send no events for buttons.
+// Only presses are reported, so holding a button doesn't repeat it.
+
+use wasm_bindgen::prelude::*;
+
+// buttons of the standard mapping
+const BUTTON_A: u32 = 0;
+const BUTTON_B: u32 = 1;
+const BUTTON_LEFT_SHOULDER: u32 = 4;
+const BUTTON_RIGHT_SHOULDER: u32 = 5;
+const BUTTON_START: u32 = 9;
+const BUTTON_UP: u32 = 12;
+const BUTTON_DOWN: u32 = 13;
+const BUTTON_LEFT: u32 = 14;
+const BUTTON_RIGHT: u32 = 15;
+// how far the left stick has to be pushed to count as a direction
+const AXIS_THRESHOLD: f64 = 0.5;
+
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Button {
+    Up,
+    Down,
+    Left,
+    Right,
+    A,
+    B,
+    LeftShoulder,
+    RightShoulder,
+    Start,
+}
+
+const BUTTONS: [Button; 9] = [
+    Button::Up, Button::Down, Button::Left, Button::Right, Button::A, Button::B, Button::LeftShoulder, Button::RightShoulder, Button::Start,
+];
+
+#[derive(Default)]
+pub struct Gamepads {
+    // buttons held at the last poll, in the order of BUTTONS
+    held: [bool; 9],
+}
+
+impl Gamepads {
+    // buttons pressed since the last poll, on any gamepad
+    pub fn poll(&mut self) -> Vec<Button> {
+        let held = held_buttons();
+        let pressed = BUTTONS.iter().enumerate()
+            .filter(|(i, _)| held[*i] && !self.held[*i])
+            .map(|(_, button)| *button)
+            .collect();
+        self.held = held;
+        pressed
+    }
+}
+
+fn held_buttons() -> [bool; 9] {
+    let mut held = [false; 9];
+    let gamepads = match web_sys::window().unwrap().navigator().get_gamepads() {
+        Ok(gamepads) => gamepads,
+        Err(_) => return held,
+    };
+    for gamepad in gamepads.iter() {
+        let gamepad = match gamepad.dyn_into::<web_sys::Gamepad>() {
+            Ok(gamepad) => gamepad,
+            // empty slots are null
+            Err(_) => continue,
+        };
+        let buttons = gamepad.buttons();
+        let is_pressed = |index: u32| buttons.get(index).dyn_into::<web_sys::GamepadButton>().is_ok_and(|button| button.pressed());
+        let axes = gamepad.axes();
+        let axis = |index: u32| axes.get(index).as_f64().unwrap_or(0.0);
+        let (x, y) = (axis(0), axis(1));
+        let states = [
+            is_pressed(BUTTON_UP) || y < -AXIS_THRESHOLD,
+            is_pressed(BUTTON_DOWN) || y > AXIS_THRESHOLD,
+            is_pressed(BUTTON_LEFT) || x < -AXIS_THRESHOLD,
+            is_pressed(BUTTON_RIGHT) || x > AXIS_THRESHOLD,
+            is_pressed(BUTTON_A),
+            is_pressed(BUTTON_B),
+            is_pressed(BUTTON_LEFT_SHOULDER),
+            is_pressed(BUTTON_RIGHT_SHOULDER),
+            is_pressed(BUTTON_START),
+        ];
+        for (held, state) in held.iter_mut().zip(states) {
+            *held |= state;
+        }
+    }
+    held
+}
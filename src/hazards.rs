@@ -0,0 +1,170 @@
+// Entities moving on the board on their own: patrolling blocks, bouncing balls and hunters.
+// They are part of the simulation and move on their own timers, counted in ticks.
+// On hexagonal boards they move between neighbouring cells in the six directions.
+
+use crate::hex;
+use crate::hex::HexDirection;
+use crate::world::{BoardType, Point};
+use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HazardKind {
+    // moves back and forth on a line
+    Patrol,
+    // moves diagonally, bouncing off walls
+    Bouncer,
+    // follows the shortest path to the snake's head
+    Hunter,
+}
+
+impl HazardKind {
+    // ticks between two moves
+    fn period(&self) -> u32 {
+        match self {
+            HazardKind::Patrol => 2,
+            HazardKind::Bouncer => 2,
+            HazardKind::Hunter => 3,
+        }
+    }
+
+    fn initial_velocity(&self, horizontal: bool) -> Point {
+        match self {
+            HazardKind::Patrol if horizontal => Point{x: 1, y: 0},
+            HazardKind::Patrol => Point{x: 0, y: 1},
+            HazardKind::Bouncer => Point{x: 1, y: 1},
+            HazardKind::Hunter => Point{x: 0, y: 0},
+        }
+    }
+
+    fn initial_hex_direction(&self, horizontal: bool) -> HexDirection {
+        match self {
+            HazardKind::Patrol if horizontal => HexDirection::SouthEast,
+            HazardKind::Bouncer => HexDirection::NorthEast,
+            _ => HexDirection::South,
+        }
+    }
+}
+
+
+#[derive(Clone)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    pub position: Point,
+    velocity: Point,
+    // instead of the velocity on hexagonal boards
+    hex_direction: HexDirection,
+    ticks_until_move: u32,
+}
+
+impl Hazard {
+    pub fn new(kind: HazardKind, position: Point, horizontal: bool) -> Self {
+        Self {
+            kind,
+            position,
+            velocity: kind.initial_velocity(horizontal),
+            hex_direction: kind.initial_hex_direction(horizontal),
+            ticks_until_move: kind.period(),
+        }
+    }
+
+    // Moves the hazard if its timer ran out. `is_free` tells whether a hazard may enter a cell,
+    // `neighbours` gives the cells around a cell, always in the same order of directions.
+    pub fn update(&mut self, board: BoardType, is_free: &dyn Fn(Point) -> bool, neighbours: &dyn Fn(Point) -> Vec<Point>, target: Point, columns: i32, rows: i32) {
+        self.ticks_until_move -= 1;
+        if self.ticks_until_move > 0 {
+            return;
+        }
+        self.ticks_until_move = self.kind.period();
+        match (self.kind, board) {
+            (HazardKind::Patrol, BoardType::Square) => self.patrol(is_free),
+            (HazardKind::Bouncer, BoardType::Square) => self.bounce(is_free),
+            (HazardKind::Patrol, BoardType::Hex) => {
+                let direction = self.hex_direction;
+                self.move_hex(is_free, &[direction, direction.opposite()]);
+            },
+            (HazardKind::Bouncer, BoardType::Hex) => {
+                let direction = self.hex_direction;
+                self.move_hex(is_free, &[direction, direction.mirrored_horizontally(), direction.mirrored_vertically(), direction.opposite()]);
+            },
+            (HazardKind::Hunter, _) => {
+                if let Some(next) = next_step_towards(self.position, target, is_free, neighbours, columns, rows) {
+                    self.position = next;
+                }
+            },
+        }
+    }
+
+    // moves in the first direction that is free and keeps it
+    fn move_hex(&mut self, is_free: &dyn Fn(Point) -> bool, directions: &[HexDirection]) {
+        for direction in directions {
+            let next = hex::neighbour(self.position, *direction);
+            if is_free(next) {
+                self.hex_direction = *direction;
+                self.position = next;
+                return;
+            }
+        }
+    }
+
+    fn patrol(&mut self, is_free: &dyn Fn(Point) -> bool) {
+        for velocity in [self.velocity, Point{x: -self.velocity.x, y: -self.velocity.y}] {
+            let next = Point{x: self.position.x + velocity.x, y: self.position.y + velocity.y};
+            if is_free(next) {
+                self.velocity = velocity;
+                self.position = next;
+                return;
+            }
+        }
+    }
+
+    fn bounce(&mut self, is_free: &dyn Fn(Point) -> bool) {
+        let Point{x: dx, y: dy} = self.velocity;
+        for velocity in [Point{x: dx, y: dy}, Point{x: -dx, y: dy}, Point{x: dx, y: -dy}, Point{x: -dx, y: -dy}] {
+            let next = Point{x: self.position.x + velocity.x, y: self.position.y + velocity.y};
+            if is_free(next) {
+                self.velocity = velocity;
+                self.position = next;
+                return;
+            }
+        }
+    }
+}
+
+
+// First step of a shortest path from `from` to `to` (breadth first search), None if there is no path.
+fn next_step_towards(from: Point, to: Point, is_free: &dyn Fn(Point) -> bool, neighbours: &dyn Fn(Point) -> Vec<Point>, columns: i32, rows: i32) -> Option<Point> {
+    if columns <= 0 || rows <= 0 {
+        return None;
+    }
+    let index = |p: Point| (p.y * columns + p.x) as usize;
+    let in_bounds = |p: Point| p.x >= 0 && p.y >= 0 && p.x < columns && p.y < rows;
+    // cell we came from, to walk the path back
+    let mut came_from: Vec<Option<Point>> = vec![None; (columns * rows) as usize];
+    let mut queue = VecDeque::new();
+    if !in_bounds(from) {
+        return None;
+    }
+    came_from[index(from)] = Some(from);
+    queue.push_back(from);
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            let mut step = current;
+            while let Some(previous) = came_from[index(step)] {
+                if previous == from {
+                    return Some(step);
+                }
+                step = previous;
+            }
+            return None;
+        }
+        for next in neighbours(current) {
+            if in_bounds(next) && came_from[index(next)].is_none() && (next == to || is_free(next)) {
+                came_from[index(next)] = Some(current);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
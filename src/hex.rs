@@ -0,0 +1,159 @@
+// Hexagonal board: flat-topped cells in six directions.
+// Cells are stored in "odd-q" offset coordinates (odd columns shifted down by half a cell), so walls,
+// food and collisions keep using Point; moving and distances go through axial coordinates.
+
+use crate::world::Point;
+
+// height of a cell relative to its width
+const HEIGHT_FACTOR: f64 = 0.866_025_403_784_438_6; // sqrt(3) / 2
+
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct Axial {
+    pub q: i32,
+    pub r: i32,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum HexDirection {
+    North,
+    NorthEast,
+    SouthEast,
+    South,
+    SouthWest,
+    NorthWest,
+}
+
+impl HexDirection {
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::North,
+        HexDirection::NorthEast,
+        HexDirection::SouthEast,
+        HexDirection::South,
+        HexDirection::SouthWest,
+        HexDirection::NorthWest,
+    ];
+
+    fn step(&self) -> Axial {
+        match self {
+            HexDirection::North => Axial{q: 0, r: -1},
+            HexDirection::NorthEast => Axial{q: 1, r: -1},
+            HexDirection::SouthEast => Axial{q: 1, r: 0},
+            HexDirection::South => Axial{q: 0, r: 1},
+            HexDirection::SouthWest => Axial{q: -1, r: 1},
+            HexDirection::NorthWest => Axial{q: -1, r: 0},
+        }
+    }
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            HexDirection::North => HexDirection::South,
+            HexDirection::NorthEast => HexDirection::SouthWest,
+            HexDirection::SouthEast => HexDirection::NorthWest,
+            HexDirection::South => HexDirection::North,
+            HexDirection::SouthWest => HexDirection::NorthEast,
+            HexDirection::NorthWest => HexDirection::SouthEast,
+        }
+    }
+
+    // the direction pointing the other way left and right, for bouncing off walls at the side
+    pub fn mirrored_horizontally(&self) -> Self {
+        match self {
+            HexDirection::NorthEast => HexDirection::NorthWest,
+            HexDirection::SouthEast => HexDirection::SouthWest,
+            HexDirection::SouthWest => HexDirection::SouthEast,
+            HexDirection::NorthWest => HexDirection::NorthEast,
+            direction => *direction,
+        }
+    }
+
+    // the direction pointing the other way up and down, for bouncing off walls above or below
+    pub fn mirrored_vertically(&self) -> Self {
+        match self {
+            HexDirection::North => HexDirection::South,
+            HexDirection::NorthEast => HexDirection::SouthEast,
+            HexDirection::SouthEast => HexDirection::NorthEast,
+            HexDirection::South => HexDirection::North,
+            HexDirection::SouthWest => HexDirection::NorthWest,
+            HexDirection::NorthWest => HexDirection::SouthWest,
+        }
+    }
+
+    // keys q w e above a s d, laid out like the directions
+    pub fn from_input(input: char) -> Option<Self> {
+        match input {
+            'q' => Some(HexDirection::NorthWest),
+            'w' => Some(HexDirection::North),
+            'e' => Some(HexDirection::NorthEast),
+            'a' => Some(HexDirection::SouthWest),
+            's' => Some(HexDirection::South),
+            'd' => Some(HexDirection::SouthEast),
+            _ => None,
+        }
+    }
+}
+
+
+pub fn to_axial(point: Point) -> Axial {
+    Axial{q: point.x, r: point.y - (point.x - (point.x & 1)) / 2}
+}
+
+pub fn from_axial(axial: Axial) -> Point {
+    Point{x: axial.q, y: axial.r + (axial.q - (axial.q & 1)) / 2}
+}
+
+pub fn neighbour(point: Point, direction: HexDirection) -> Point {
+    let axial = to_axial(point);
+    let step = direction.step();
+    from_axial(Axial{q: axial.q + step.q, r: axial.r + step.r})
+}
+
+pub fn neighbours(point: Point) -> [Point; 6] {
+    HexDirection::ALL.map(|direction| neighbour(point, direction))
+}
+
+// number of moves between two cells
+pub fn distance(a: Point, b: Point) -> u32 {
+    let (a, b) = (to_axial(a), to_axial(b));
+    let (dq, dr) = (a.q - b.q, a.r - b.r);
+    ((dq.abs() + dr.abs() + (dq + dr).abs()) / 2) as u32
+}
+
+
+// pixel position of the centre of a cell, block_size being the width of a cell
+pub fn center(point: Point, block_size: f64) -> (f64, f64) {
+    let height = block_size * HEIGHT_FACTOR;
+    let x = block_size / 2.0 + point.x as f64 * block_size * 0.75;
+    let y = height / 2.0 * (1 + (point.x & 1)) as f64 + point.y as f64 * height;
+    (x, y)
+}
+
+// the cell whose centre is nearest in a straight grid, at most one cell off the cell at the position
+pub fn approximate_cell(x: f64, y: f64, block_size: f64) -> Point {
+    let column = ((x - block_size / 2.0) / (block_size * 0.75)).round() as i32;
+    let row = ((y - block_size * HEIGHT_FACTOR / 2.0) / (block_size * HEIGHT_FACTOR)).round() as i32;
+    Point{x: column, y: row}
+}
+
+// corners of a cell, clockwise from the right one
+pub fn corners(point: Point, block_size: f64) -> [(f64, f64); 6] {
+    let (x, y) = center(point, block_size);
+    let radius = block_size / 2.0;
+    [0, 1, 2, 3, 4, 5].map(|i| {
+        let angle = std::f64::consts::PI / 3.0 * i as f64;
+        (x + radius * angle.cos(), y + radius * angle.sin())
+    })
+}
+
+// size of a board in cell widths
+pub fn size_in_cells(columns: i32, rows: i32) -> (f64, f64) {
+    let width = columns as f64 * 0.75 + 0.25;
+    let height = (rows as f64 + 0.5) * HEIGHT_FACTOR;
+    (width, height)
+}
+
+// pixel size of a board with the given columns and rows
+pub fn pixel_size(columns: i32, rows: i32, block_size: u32) -> (u32, u32) {
+    let (width, height) = size_in_cells(columns, rows);
+    ((width * block_size as f64).ceil() as u32, (height * block_size as f64).ceil() as u32)
+}
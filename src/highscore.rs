@@ -1,134 +1,350 @@
 use crate::utils::log;
 use crate::utils::format_duration;
+use crate::i18n;
+use crate::replay;
+use crate::replay::Replay;
+use crate::scoring;
+use crate::scoring::{legacy_weight, ScoringRules};
+use crate::world::{GameMode, World};
 use core::cmp::Ordering;
 use serde::{Deserialize, Serialize};
-use chrono::offset::Local;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
 
 use wasm_bindgen::prelude::*;
 
 const STORAGE_KEY: &str = "highscore";
 const MAX_ENTRIES: usize = 20;
+// more entries than shown are kept, so the leaderboards of the current month are complete
+const MAX_STORED_ENTRIES: usize = 200;
+// only the best entries keep their replay, the others are shown as unverified
+const MAX_REPLAYS: usize = MAX_ENTRIES;
+const LEGACY_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
 
-const WEIGHT_FAST: u32 = 10;
-const WEIGHT_LONG: u32 = 3;
 
-
-#[derive(Serialize, Deserialize, Eq, PartialEq, Ord)]
-struct HighscoreEntry {
-    name: String,
-    score: u32,
+#[derive(Serialize, Deserialize, Eq, PartialEq)]
+pub struct HighscoreEntry {
+    pub name: String,
+    pub score: u32,
     #[serde(default = "default_duration")]
-    duration: u32,
+    pub duration: u32,
+    // input mode id, "keyboard" or "touch"
     mode: String,
+    // game mode id as in WorldConfig::mode_id()
     #[serde(default = "default_game_mode")]
-    game_mode: String,
+    pub game_mode: String,
+    // unique id, used to highlight the latest entry
+    #[serde(default)]
+    id: String,
+    // RFC 3339, UTC
+    #[serde(default)]
+    pub timestamp: String,
+    // local time, only in entries stored before timestamps were added
+    #[serde(default, skip_serializing)]
     time: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    replay: Option<Replay>,
+    // weight of the game mode and score weighted with it, according to the scoring rules of the game
+    #[serde(default)]
+    weight: Option<u32>,
+    #[serde(default)]
+    weighted_score: Option<u32>,
+    // points per food of the scoring rules, for entries played before scoring rules existed
+    #[serde(skip)]
+    legacy_scale: Option<u32>,
+    // result of verifying the replay; only kept in memory, as a stored result could be faked
+    #[serde(skip)]
+    verification: Option<Result<(), String>>,
 }
 
 fn default_game_mode() -> String {
-    "Fast Snake".to_string()
+    GameMode::FAST.id().to_string()
 }
 
 fn default_duration() -> u32 {
     (99 * 60 + 59) * 1000
 }
 
+impl HighscoreEntry {
+    fn local_time(&self) -> Option<DateTime<Local>> {
+        DateTime::parse_from_rfc3339(&self.timestamp).ok().map(|t| t.with_timezone(&Local))
+    }
+
+    // "%Y-%m-%d" in the viewer's local time zone
+    pub fn local_date(&self) -> String {
+        match self.local_time() {
+            Some(time) => time.format("%Y-%m-%d").to_string(),
+            None => String::new(),
+        }
+    }
+
+    // converts entries stored before timestamps and ids were added, or with the English names of the modes
+    fn migrate(&mut self) {
+        if self.timestamp.is_empty() {
+            let local_time = NaiveDateTime::parse_from_str(&self.time, LEGACY_TIME_FORMAT).ok()
+                .and_then(|t| Local.from_local_datetime(&t).earliest());
+            self.timestamp = match local_time {
+                Some(local_time) => format_timestamp(local_time.with_timezone(&Utc)),
+                None => format_timestamp(DateTime::UNIX_EPOCH),
+            };
+        }
+        if self.id.is_empty() {
+            self.id = new_id(&self.timestamp);
+        }
+        self.mode = self.mode.to_lowercase();
+        self.game_mode = legacy_game_mode_id(&self.game_mode);
+    }
+
+    // entries without a replay stay unverified
+    fn verify(&mut self) {
+        self.verification = self.replay.as_ref().map(|replay|
+            replay::verify(replay, &self.scoring_rules(), self.score, self.played_weighted_score(), self.duration, &self.game_mode)
+                .map_err(|e| e.to_string()));
+    }
+
+    // Scores from before scoring rules existed were a point per food, they are scaled to the points per food
+    // of the scoring rules to be ranked with newer ones. The stored score stays as played, to verify the replay.
+    fn scale_legacy_score(&mut self, points_per_food: u32) {
+        if self.weighted_score.is_none() {
+            self.legacy_scale = Some(points_per_food);
+        }
+    }
+
+    // entries without a weighted score were played before scoring rules existed
+    fn scoring_rules(&self) -> ScoringRules {
+        match self.weighted_score {
+            Some(_) => scoring::default_rules(),
+            None => ScoringRules::default(),
+        }
+    }
+
+    fn weight(&self) -> u32 {
+        self.weight.unwrap_or_else(|| legacy_weight(&self.game_mode))
+    }
+    fn weighted_score(&self) -> u32 {
+        self.played_weighted_score() * self.legacy_scale.unwrap_or(1)
+    }
+    fn played_weighted_score(&self) -> u32 {
+        self.weighted_score.unwrap_or_else(|| self.score * self.weight())
+    }
+    // time attack games are only ranked against games with the same time budget
+    fn ranking_group(&self) -> &str {
+        if self.game_mode.starts_with(GameMode::TIME_ATTACK.id()) {&self.game_mode} else {""}
+    }
+}
+
 impl PartialOrd for HighscoreEntry {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HighscoreEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
         if self.mode == other.mode {
-            let this_weight = if self.game_mode.starts_with("Fast") {WEIGHT_FAST} else {WEIGHT_LONG};
-            let other_weight = if other.game_mode.starts_with("Fast") {WEIGHT_FAST} else {WEIGHT_LONG};
-            let this_weighted_score = self.score * this_weight;
-            let other_weighted_score = other.score * other_weight;
+            if self.ranking_group() != other.ranking_group() {
+                return self.ranking_group().cmp(other.ranking_group());
+            }
+            let this_weighted_score = self.weighted_score();
+            let other_weighted_score = other.weighted_score();
             if this_weighted_score == other_weighted_score {
                 if self.duration == other.duration {
-                    return Some(self.time.cmp(&other.time));
+                    return self.timestamp.cmp(&other.timestamp);
                 }
-                return Some(self.duration.cmp(&other.duration));
+                return self.duration.cmp(&other.duration);
             }
-            return Some(other_weighted_score.cmp(&this_weighted_score));
+            return other_weighted_score.cmp(&this_weighted_score);
         }
-        return Some(self.mode.cmp(&other.mode));
+        self.mode.cmp(&other.mode)
     }
 }
 
 
-pub fn add_score(name :&str, score :u32, input_mode :&str, game_mode :&str, duration :u32) -> Option<String> {
-    let window = web_sys::window().unwrap();
-    let local_storage_opt = window.local_storage().unwrap();
-    if local_storage_opt.is_some() {
-        let local_storage = local_storage_opt.unwrap();
-        let json_result = local_storage.get_item(STORAGE_KEY);
-        let mut entries :Vec<HighscoreEntry>;
-        if json_result.is_ok() {
-            let json_opt = json_result.unwrap();
-            if json_opt.is_some() {
-                let json = json_opt.unwrap();
-                entries = serde_json::from_str(&json).unwrap();
-            } else {
-                entries = Vec::new();
-            }
-        } else {
-            entries = Vec::new();
+// id of a game mode stored by its English name, like "Fast Snake" or "Time Attack 60s"; ids are kept
+pub fn legacy_game_mode_id(name: &str) -> String {
+    if let Some(seconds) = name.strip_prefix("Time Attack ").and_then(|rest| rest.strip_suffix('s')) {
+        return format!("{}-{}", GameMode::TIME_ATTACK.id(), seconds);
+    }
+    let game_mode = match name {
+        "Fast Snake" => GameMode::FAST,
+        "Long Snake" => GameMode::LONG,
+        "Campaign" => GameMode::CAMPAIGN,
+        "Time Attack" => GameMode::TIME_ATTACK,
+        "Survival" => GameMode::SURVIVAL,
+        "Versus" => GameMode::VERSUS,
+        "Puzzle" => GameMode::PUZZLE,
+        id => return id.to_string(),
+    };
+    game_mode.id().to_string()
+}
+
+fn format_timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn new_id(timestamp: &str) -> String {
+    format!("{}-{:x}", timestamp, crate::utils::random_seed())
+}
+
+
+#[derive(Clone, Copy, PartialEq)]
+enum Range {
+    Today,
+    Week,
+    Month,
+    All,
+}
+
+impl Range {
+    fn from_value(value: &str) -> Self {
+        match value {
+            "today" => Range::Today,
+            "week" => Range::Week,
+            "month" => Range::Month,
+            _ => Range::All,
         }
+    }
 
-        let new_entry = HighscoreEntry {
-            name: name.to_string(),
-            score: score,
-            duration: duration,
-            mode: input_mode.to_string(),
-            game_mode: game_mode.to_string(),
-            time: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    // in the viewer's local time zone
+    fn contains(&self, entry: &HighscoreEntry, now: &DateTime<Local>) -> bool {
+        let time = match entry.local_time() {
+            Some(time) => time,
+            None => return *self == Range::All,
         };
-        let new_entry_time = new_entry.time.clone();
-        entries.push(new_entry);
-        entries.sort();
-        while entries.len() > MAX_ENTRIES {
-            entries.pop();
+        match self {
+            Range::Today => time.date_naive() == now.date_naive(),
+            Range::Week => {
+                let monday = now.date_naive() - Duration::days(now.weekday().num_days_from_monday() as i64);
+                time.date_naive() >= monday && time <= *now
+            },
+            Range::Month => time.year() == now.year() && time.month() == now.month(),
+            Range::All => true,
         }
-        let json = serde_json::to_string(&entries).unwrap();
+    }
+}
+
+fn selected_range() -> Range {
+    let document = web_sys::window().unwrap().document().unwrap();
+    match document.get_element_by_id("highscore-range") {
+        Some(element) => Range::from_value(&element.dyn_into::<web_sys::HtmlSelectElement>().unwrap().value()),
+        None => Range::All,
+    }
+}
+
+
+pub fn load_entries() -> Vec<HighscoreEntry> {
+    let window = web_sys::window().unwrap();
+    let mut entries :Vec<HighscoreEntry> = match window.local_storage() {
+        Ok(Some(local_storage)) => match local_storage.get_item(STORAGE_KEY) {
+            Ok(Some(json)) => serde_json::from_str(&json).unwrap(),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    };
+    entries.iter_mut().for_each(HighscoreEntry::migrate);
+    let points_per_food = scoring::default_rules().points_per_food;
+    for entry in entries.iter_mut() {
+        entry.verify();
+        entry.scale_legacy_score(points_per_food);
+    }
+    entries
+}
+
+fn save_entries(entries: &[HighscoreEntry]) -> bool {
+    let window = web_sys::window().unwrap();
+    if let Ok(Some(local_storage)) = window.local_storage() {
+        let json = serde_json::to_string(entries).unwrap();
         let result = local_storage.set_item(STORAGE_KEY, &json);
         if result.is_err() {
             log!("could not save highscore to local_storage: {}", result.err().unwrap().as_string().unwrap());
         } else {
-            return Some(new_entry_time);
+            return true;
         }
     }
-    return None;
+    false
+}
+
+pub fn add_score(name :&str, input_mode :&str, world :&World) -> Option<String> {
+    let mut entries = load_entries();
+
+    let timestamp = format_timestamp(Utc::now());
+    let mut new_entry = HighscoreEntry {
+        name: name.to_string(),
+        score: world.score(),
+        duration: world.duration(),
+        mode: input_mode.to_string(),
+        game_mode: world.config().mode_id(),
+        id: new_id(&timestamp),
+        timestamp,
+        time: String::new(),
+        replay: Some(world.replay()),
+        weight: Some(world.config().scoring.weight(world.game_mode())),
+        weighted_score: Some(world.weighted_score()),
+        legacy_scale: None,
+        verification: None,
+    };
+    new_entry.verify();
+    let new_entry_id = new_entry.id.clone();
+    entries.push(new_entry);
+    entries.sort();
+    // keep the all time best entries and everything from this month
+    let now = Local::now();
+    let mut i = 0;
+    entries.retain(|entry| {
+        i += 1;
+        i <= MAX_ENTRIES || Range::Month.contains(entry, &now)
+    });
+    entries.truncate(MAX_STORED_ENTRIES);
+    entries.iter_mut().skip(MAX_REPLAYS).for_each(|entry| {
+        entry.replay = None;
+        entry.verification = None;
+    });
+    if save_entries(&entries) {
+        return Some(new_entry_id);
+    }
+    None
 }
 
-pub fn print_highscores(latest_timestamp :Option<String>) {
+// all time rank of an entry among the entries of its input mode and ranking group
+pub fn rank(id: &str) -> Option<u32> {
+    let entries = load_entries();
+    let entry = entries.iter().find(|entry| entry.id == id)?;
+    let rank = entries.iter()
+        .filter(|other| other.mode == entry.mode && other.ranking_group() == entry.ranking_group())
+        .position(|other| other.id == id)?;
+    Some(rank as u32 + 1)
+}
+
+pub fn print_highscores(latest_id :Option<String>) {
     let window = web_sys::window().unwrap();
-    let local_storage_opt = window.local_storage().unwrap();
-    if local_storage_opt.is_some() {
-        let local_storage = local_storage_opt.unwrap();
-        let document = window.document().unwrap();
-        let table = document.get_element_by_id("highscores-table").unwrap().dyn_into::<web_sys::HtmlElement>().unwrap();
-        while table.child_element_count() > 1 {
-            table.last_element_child().unwrap().remove();
+    let document = window.document().unwrap();
+    let table = document.get_element_by_id("highscores-table").unwrap().dyn_into::<web_sys::HtmlElement>().unwrap();
+    while table.child_element_count() > 1 {
+        table.last_element_child().unwrap().remove();
+    }
+    let range = selected_range();
+    let now = Local::now();
+    let entries = load_entries().into_iter()
+        .filter(|entry| range.contains(entry, &now))
+        .take(MAX_ENTRIES);
+    let mut rank = 0;
+    let mut previous_group: Option<(String, String)> = None;
+    for entry in entries {
+        // ranks start over for every input mode and ranking group
+        let group = (entry.mode.clone(), entry.ranking_group().to_string());
+        if previous_group.as_ref() != Some(&group) {
+            rank = 0;
+            previous_group = Some(group);
         }
-        let json_result = local_storage.get_item(STORAGE_KEY);
-        let entries :Vec<HighscoreEntry>;
-        if json_result.is_ok() {
-            let json_opt = json_result.unwrap();
-            if json_opt.is_some() {
-                let json = json_opt.unwrap();
-                entries = serde_json::from_str(&json).unwrap();
-                let mut i = 0;
-                for entry in entries {
-                    let result = print_entry(
-                        &document,
-                        &table,
-                        &entry,
-                        i+1,
-                        latest_timestamp.is_some() && latest_timestamp.as_ref().unwrap() == &entry.time);
-                    if result.is_err() {
-                        log!("could not crate highscore table elements: {}", result.err().unwrap().as_string().unwrap());
-                        break;
-                    }
-                    i += 1;
-                }
-            }
+        rank += 1;
+        let result = print_entry(
+            &document,
+            &table,
+            &entry,
+            rank,
+            latest_id.as_ref() == Some(&entry.id));
+        if result.is_err() {
+            log!("could not crate highscore table elements: {}", result.err().unwrap().as_string().unwrap());
+            break;
         }
     }
 }
@@ -151,7 +367,9 @@ fn print_entry(
     let td_game_mode = document.create_element("td").unwrap();
     let td_mode = document.create_element("td").unwrap();
     let td_weight = document.create_element("td").unwrap();
+    let td_points = document.create_element("td").unwrap();
     let td_time = document.create_element("td").unwrap();
+    let td_verified = document.create_element("td").unwrap();
     tr.append_child(&td_rank)?;
     tr.append_child(&td_name)?;
     tr.append_child(&td_score)?;
@@ -159,16 +377,49 @@ fn print_entry(
     tr.append_child(&td_game_mode)?;
     tr.append_child(&td_mode)?;
     tr.append_child(&td_weight)?;
+    tr.append_child(&td_points)?;
     tr.append_child(&td_time)?;
+    tr.append_child(&td_verified)?;
     td_rank.set_text_content(Some(&rank.to_string()));
     td_name.set_text_content(Some(&entry.name));
     td_duration.set_text_content(Some(&(format_duration(entry.duration)).to_string()));
     td_score.set_text_content(Some(&entry.score.to_string()));
-    td_game_mode.set_text_content(Some(&entry.game_mode));
-    td_mode.set_text_content(Some(&entry.mode));
-    let weigth_val_float :f32 = (if entry.game_mode.starts_with("Fast") {WEIGHT_FAST} else {WEIGHT_LONG}) as f32 / 10.;
-    let weight_val_str = if entry.game_mode.starts_with("Fast") {weigth_val_float.to_string()} else {format!("{:.1}", weigth_val_float)};
+    td_game_mode.set_text_content(Some(&i18n::mode_name(&entry.game_mode)));
+    td_mode.set_text_content(Some(&i18n::input_mode_name(&entry.mode)));
+    let weigth_val_float :f32 = entry.weight() as f32 / 10.;
+    let weight_val_str = if entry.weight().is_multiple_of(10) {weigth_val_float.to_string()} else {format!("{:.1}", weigth_val_float)};
     td_weight.set_text_content(Some(&weight_val_str));
-    td_time.set_text_content(Some(&entry.time));
+    td_points.set_text_content(Some(&entry.weighted_score().to_string()));
+    let time = match entry.local_time() {
+        Some(time) => time.format(LEGACY_TIME_FORMAT).to_string(),
+        None => entry.timestamp.clone(),
+    };
+    td_time.set_text_content(Some(&time));
+    print_verified_badge(document, &td_verified, entry)?;
+    Ok(())
+}
+
+fn print_verified_badge(
+        document :&web_sys::Document,
+        td :&web_sys::Element,
+        entry :&HighscoreEntry)
+        -> Result<(), wasm_bindgen::JsValue> {
+    let verification = match &entry.verification {
+        Some(verification) => verification.clone(),
+        None => Err(i18n::text("highscore.no-replay")),
+    };
+    let badge = document.create_element("span").unwrap();
+    td.append_child(&badge)?;
+    match verification {
+        Ok(()) => {
+            badge.set_class_name("verified");
+            badge.set_text_content(Some(&i18n::text("highscore.verified")));
+        },
+        Err(reason) => {
+            badge.set_class_name("unverified");
+            badge.set_text_content(Some("-"));
+            badge.set_attribute("title", &reason)?;
+        },
+    }
     Ok(())
 }
@@ -0,0 +1,126 @@
+// Texts shown to the player, in the language chosen with the language select. messages.json has a table
+// of messages per language, a missing message falls back to English and then to its key.
+// Stored data only holds language-neutral ids, like the game mode ids, which are turned into text when shown.
+// Messages take arguments as {0}, {1}, ...
+
+use crate::utils::log;
+use crate::utils::local_storage;
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+const MESSAGES: &str = include_str!("../messages.json");
+const STORAGE_KEY: &str = "language";
+
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Language {
+    English,
+    German,
+}
+
+impl Language {
+    // also takes browser languages like "de-AT"
+    pub fn from_value(value: &str) -> Self {
+        if value.starts_with("de") {Language::German} else {Language::English}
+    }
+
+    pub fn value(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
+}
+
+thread_local! {
+    static LANGUAGE: Cell<Language> = const { Cell::new(Language::English) };
+    static CATALOGUE: HashMap<String, HashMap<String, String>> = serde_json::from_str(MESSAGES).unwrap();
+}
+
+pub fn language() -> Language {
+    LANGUAGE.with(Cell::get)
+}
+
+pub fn set_language(language: Language) {
+    LANGUAGE.with(|current| current.set(language));
+}
+
+
+// the stored language, or the one of the browser on first run
+pub fn load_language() -> Language {
+    let stored = local_storage().and_then(|local_storage| local_storage.get_item(STORAGE_KEY).ok().flatten());
+    match stored {
+        Some(value) => Language::from_value(&value),
+        None => Language::from_value(&web_sys::window().unwrap().navigator().language().unwrap_or_default()),
+    }
+}
+
+pub fn save_language(language: Language) {
+    if let Some(local_storage) = local_storage() {
+        if let Err(e) = local_storage.set_item(STORAGE_KEY, language.value()) {
+            log!("could not save language to local_storage: {:?}", e.as_string());
+        }
+    }
+}
+
+
+pub fn text(key: &str) -> String {
+    CATALOGUE.with(|catalogue| {
+        [language().value(), Language::English.value()].iter()
+            .find_map(|language| catalogue.get(*language).and_then(|messages| messages.get(key)))
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    })
+}
+
+pub fn format(key: &str, args: &[&str]) -> String {
+    args.iter().enumerate()
+        .fold(text(key), |message, (i, arg)| message.replace(&format!("{{{}}}", i), arg))
+}
+
+// name of a game mode id as in WorldConfig::mode_id()
+pub fn mode_name(mode_id: &str) -> String {
+    match mode_id.strip_prefix("time-attack-") {
+        Some(seconds) => format("mode.time-attack-budget", &[seconds]),
+        None => text(&format!("mode.{}", mode_id)),
+    }
+}
+
+// name of an input mode id, "keyboard" or "touch"
+pub fn input_mode_name(input_mode: &str) -> String {
+    text(&format!("input.{}", input_mode))
+}
+
+
+// Writes the static texts of the page: the text of elements with a data-i18n attribute, and the
+// aria-label or placeholder of elements with data-i18n-label or data-i18n-placeholder.
+pub fn translate_page(document: &web_sys::Document) {
+    if let Some(root) = document.document_element() {
+        crate::utils::handle_js_error(root.set_attribute("lang", language().value()));
+    }
+    let attributes = [("data-i18n", None), ("data-i18n-label", Some("aria-label")), ("data-i18n-placeholder", Some("placeholder"))];
+    for (attribute, target) in attributes {
+        let result = translate_elements(document, attribute, target);
+        if result.is_err() {
+            log!("could not translate page: {:?}", result.err().unwrap().as_string());
+        }
+    }
+}
+
+fn translate_elements(document: &web_sys::Document, attribute: &str, target: Option<&str>) -> Result<(), JsValue> {
+    let elements = document.query_selector_all(&format!("[{}]", attribute))?;
+    for i in 0..elements.length() {
+        let element = match elements.item(i).map(|node| node.dyn_into::<web_sys::Element>()) {
+            Some(Ok(element)) => element,
+            _ => continue,
+        };
+        let message = text(&element.get_attribute(attribute).unwrap_or_default());
+        match target {
+            Some(target) => element.set_attribute(target, &message)?,
+            None => element.set_text_content(Some(&message)),
+        }
+    }
+    Ok(())
+}
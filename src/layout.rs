@@ -0,0 +1,96 @@
+// Sizing of the canvas. The board is defined in cells; the cell size follows the viewport, up to the
+// cell size chosen in the form. Boards that would need cells smaller than MIN_BLOCK_SIZE keep the chosen
+// cell size, and the canvas shows the part of them around the snake.
+// The canvas backing store is scaled by devicePixelRatio, while drawing happens in CSS pixels, so the
+// board stays sharp on HiDPI screens.
+
+use crate::hex;
+use crate::world::BoardType;
+
+use wasm_bindgen::prelude::*;
+
+const MIN_BLOCK_SIZE: u32 = 4;
+// CSS pixels kept free around the board
+const VIEWPORT_MARGIN: f64 = 20.0;
+// below this share of the viewport height the board may need scrolling instead of shrinking further
+const MIN_HEIGHT_SHARE: f64 = 0.5;
+
+
+// sizes in CSS pixels
+pub struct Layout {
+    pub block_size: u32,
+    // of the canvas
+    pub width: u32,
+    pub height: u32,
+    // of the whole board, larger than the canvas when scrolling
+    pub board_width: u32,
+    pub board_height: u32,
+}
+
+impl Layout {
+    pub fn is_scrolling(&self) -> bool {
+        self.board_width > self.width || self.board_height > self.height
+    }
+}
+
+// largest cell size up to max_block_size fitting the board into the viewport
+pub fn fit(columns: i32, rows: i32, board: BoardType, max_block_size: u32) -> Layout {
+    let (available_width, available_height) = available_size();
+    let (cells_x, cells_y) = match board {
+        BoardType::Square => (columns as f64, rows as f64),
+        BoardType::Hex => hex::size_in_cells(columns, rows),
+    };
+    let fitting_block_size = (available_width / cells_x).min(available_height / cells_y).floor().max(0.0) as u32;
+    let block_size = if fitting_block_size < MIN_BLOCK_SIZE {
+        max_block_size.max(MIN_BLOCK_SIZE)
+    } else {
+        fitting_block_size.min(max_block_size).max(MIN_BLOCK_SIZE)
+    };
+    let (board_width, board_height) = match board {
+        BoardType::Square => (columns as u32 * block_size, rows as u32 * block_size),
+        BoardType::Hex => hex::pixel_size(columns, rows, block_size),
+    };
+    Layout {
+        block_size,
+        width: board_width.min(available_width.max(0.0) as u32),
+        height: board_height.min(available_height.max(0.0) as u32),
+        board_width,
+        board_height,
+    }
+}
+
+// Sizes the canvas and the box around it, which letterboxes the board. Resizing clears the canvas.
+pub fn apply(canvas: &web_sys::HtmlCanvasElement, context: &web_sys::CanvasRenderingContext2d, layout: &Layout) {
+    let window = web_sys::window().unwrap();
+    let ratio = window.device_pixel_ratio();
+    canvas.set_width((layout.width as f64 * ratio).round() as u32);
+    canvas.set_height((layout.height as f64 * ratio).round() as u32);
+    let style = canvas.style();
+    crate::utils::handle_js_error(style.set_property("width", &format!("{}px", layout.width)));
+    crate::utils::handle_js_error(style.set_property("height", &format!("{}px", layout.height)));
+    crate::utils::handle_js_error(context.set_transform(ratio, 0.0, 0.0, ratio, 0.0, 0.0));
+
+    let (available_width, available_height) = available_size();
+    if let Some(canvas_box) = canvas_box() {
+        let style = canvas_box.style();
+        crate::utils::handle_js_error(style.set_property("width", &format!("{}px", available_width.max(layout.width as f64))));
+        crate::utils::handle_js_error(style.set_property("height", &format!("{}px", available_height.max(layout.height as f64))));
+    }
+}
+
+fn canvas_box() -> Option<web_sys::HtmlElement> {
+    let document = web_sys::window().unwrap().document().unwrap();
+    document.get_element_by_id("canvas-box").and_then(|element| element.dyn_into::<web_sys::HtmlElement>().ok())
+}
+
+// space for the board: the viewport below the top of the canvas box, minus the touch controls if shown
+fn available_size() -> (f64, f64) {
+    let window = web_sys::window().unwrap();
+    let document = window.document().unwrap();
+    let viewport_width = window.inner_width().ok().and_then(|width| width.as_f64()).unwrap_or(0.0);
+    let viewport_height = window.inner_height().ok().and_then(|height| height.as_f64()).unwrap_or(0.0);
+    let top = canvas_box().map_or(0.0, |canvas_box| canvas_box.get_bounding_client_rect().top() + window.scroll_y().unwrap_or(0.0));
+    let touch_controls = document.get_element_by_id("touch-controls").map_or(0, |element| element.client_height());
+    let height = viewport_height - top - touch_controls as f64 - 2.0 * VIEWPORT_MARGIN;
+    (viewport_width - 2.0 * VIEWPORT_MARGIN, height.max(viewport_height * MIN_HEIGHT_SHARE))
+}
@@ -1,9 +1,35 @@
 mod utils;
 mod textdisplay;
 mod highscore;
+mod layout;
+mod camera;
+mod animations;
+mod countdown;
+mod debug;
+mod menu;
+mod gamepad;
+mod palette;
 mod game;
-
-use crate::game::Game;
+mod profile;
+mod achievements;
+pub mod ai;
+pub mod campaign;
+pub mod hazards;
+pub mod hex;
+pub mod maze;
+pub mod powerups;
+pub mod puzzle;
+mod rng;
+pub mod scoring;
+pub mod world;
+pub mod replay;
+pub mod synth;
+mod audio;
+mod i18n;
+
+use crate::game::{DisplaySettings, Game};
+use crate::gamepad::{Button, Gamepads};
+use crate::menu::{MenuInput, MenuItem};
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -15,6 +41,24 @@ use wasm_bindgen::prelude::*;
 // That requires unsafe blocks, which is ok as wasm is single threaded.
 static mut GAME: Game = Game::default();
 
+// GAME without a reference to the static mut at every use
+fn game() -> &'static mut Game {
+    unsafe { &mut *std::ptr::addr_of_mut!(GAME) }
+}
+
+// number of portal pairs when portals are switched on
+const RANDOM_PORTALS: u32 = 2;
+const GAMEPAD_POLL_INTERVAL_MS: i32 = 50;
+// seconds
+const MAX_COUNTDOWN: u32 = 9;
+// hazards when hazards are switched on
+const RANDOM_HAZARDS: [hazards::HazardKind; 4] = [
+    hazards::HazardKind::Patrol,
+    hazards::HazardKind::Patrol,
+    hazards::HazardKind::Bouncer,
+    hazards::HazardKind::Hunter,
+];
+
 
 #[wasm_bindgen(start)]
 fn start() -> Result<(), JsValue> {
@@ -23,8 +67,19 @@ fn start() -> Result<(), JsValue> {
 
     let document = web_sys::window().unwrap().document().unwrap();
     register_event_listeners(&document)?;
+    init_reduced_motion(&document);
+    init_audio_controls(&document);
+    init_language(&document);
+    profile::init();
+    profile::print_profiles();
+    achievements::print_achievements();
+    campaign::print_levels(campaign::unlocked_level());
+    puzzle::print_puzzles();
     highscore::print_highscores(None);
 
+    let (columns, rows) = board_size(&document);
+    game().show_title(columns, rows, display_settings(&document), canvas_context(&document));
+
     Ok(())
 }
 
@@ -34,6 +89,14 @@ fn register_event_listeners(document: &web_sys::Document) -> Result<(), JsValue>
     register_event_listener_create(document)?;
     register_event_listener_input_keyboard(document)?;
     register_event_listeners_input_touch(document)?;
+    register_event_listeners_profile(document)?;
+    register_event_listener_highscore_range(document)?;
+    register_event_listeners_puzzle_help(document)?;
+    register_event_listener_resize()?;
+    register_event_listener_menu_touch(document)?;
+    register_gamepad_polling()?;
+    register_event_listeners_audio(document)?;
+    register_event_listener_language(document)?;
     
     Ok(())
 }
@@ -60,17 +123,41 @@ fn register_event_listener_create(document: &web_sys::Document) -> Result<(), Js
 fn register_event_listener_input_keyboard(document: &web_sys::Document) -> Result<(), JsValue> {
     let callback_keydown = Closure::wrap(Box::new(|e: web_sys::KeyboardEvent| {
         //log!("e.key_code(): {}", e.key_code());
+        // F3
+        if e.key_code() == 114 {
+            e.prevent_default();
+            game().toggle_debug();
+            return;
+        }
         unsafe {
-            if !GAME.is_over() {
-                e.prevent_default();
-                match e.key_code() {
-                    0x41 => GAME.set_input('a'),
-                    0x53 => GAME.set_input('s'),
-                    0x44 => GAME.set_input('d'),
-                    0x57 => GAME.set_input('w'),
-                    32 => GAME.set_input(' '),
-                    _ => GAME.set_input(game::DEFAULT_INPUT),
+            if game().has_menu() {
+                // typing in the form doesn't move through menus
+                let typing = e.target().is_some_and(|target| target.dyn_into::<web_sys::HtmlInputElement>().is_ok());
+                let input = match e.key_code() {
+                    0x57 | 38 => Some(MenuInput::Up),
+                    0x53 | 40 => Some(MenuInput::Down),
+                    13 | 32 => Some(MenuInput::Select),
+                    27 => Some(MenuInput::Back),
+                    _ => None,
+                };
+                if let (false, Some(input)) = (typing, input) {
+                    e.prevent_default();
+                    menu_input(input);
                 }
+            } else if !GAME.is_over() {
+                e.prevent_default();
+                let input = match e.key_code() {
+                    0x41 => 'a',
+                    0x53 => 's',
+                    0x44 => 'd',
+                    0x57 => 'w',
+                    // north-west and north-east on hexagonal boards
+                    0x51 => 'q',
+                    0x45 => 'e',
+                    32 | 27 => ' ',
+                    _ => world::DEFAULT_INPUT,
+                };
+                GAME.set_input(input);
                 GAME.set_pressed(true);
             }
         }
@@ -97,6 +184,9 @@ fn register_event_listener_input_keyboard(document: &web_sys::Document) -> Resul
 
 fn register_event_listeners_input_touch(document: &web_sys::Document) -> Result<(), JsValue> {
     register_event_listener_input_touch(document, 'w', "touch-up")?;
+    // only shown on hexagonal boards
+    register_event_listener_input_touch(document, 'q', "touch-up-left")?;
+    register_event_listener_input_touch(document, 'e', "touch-up-right")?;
     register_event_listener_input_touch(document, 'a', "touch-left")?;
     register_event_listener_input_touch(document, 's', "touch-down")?;
     register_event_listener_input_touch(document, 'd', "touch-right")?;
@@ -106,8 +196,15 @@ fn register_event_listeners_input_touch(document: &web_sys::Document) -> Result<
 
 fn register_event_listener_input_touch(document: &web_sys::Document, key :char, id :&str) -> Result<(), JsValue> {
     let callback = Closure::wrap(Box::new(move || {
-        unsafe {
-            GAME.set_input(key);
+        if game().has_menu() {
+            match key {
+                'w' => menu_input(MenuInput::Up),
+                's' => menu_input(MenuInput::Down),
+                ' ' => menu_input(MenuInput::Select),
+                _ => (),
+            }
+        } else {
+            game().set_input(key);
         }
     }) as Box<dyn FnMut()>);
 
@@ -119,54 +216,413 @@ fn register_event_listener_input_touch(document: &web_sys::Document, key :char,
     Ok(())
 }
 
+fn register_event_listeners_profile(document: &web_sys::Document) -> Result<(), JsValue> {
+    let callback_select = Closure::wrap(Box::new(|_e: web_sys::Event| {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let profile_element = document.get_element_by_id("profile").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+        profile::select_profile(&profile_element.value());
+        profile::print_profiles();
+        achievements::print_achievements();
+    }) as Box<dyn FnMut(_)>);
+
+    let callback_add = Closure::wrap(Box::new(|_e: web_sys::Event| {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let name_element = document.get_element_by_id("new-profile-name").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+        profile::add_profile(&name_element.value());
+        name_element.set_value("");
+        profile::print_profiles();
+        achievements::print_achievements();
+    }) as Box<dyn FnMut(_)>);
+
+    document.get_element_by_id("profile").unwrap()
+        .add_event_listener_with_callback("change", callback_select.as_ref().unchecked_ref())?;
+    document.get_element_by_id("add-profile").unwrap()
+        .add_event_listener_with_callback("click", callback_add.as_ref().unchecked_ref())?;
+
+    callback_select.forget();
+    callback_add.forget();
+
+    Ok(())
+}
+
+fn register_event_listener_highscore_range(document: &web_sys::Document) -> Result<(), JsValue> {
+    let callback = Closure::wrap(Box::new(|_e: web_sys::Event| {
+        highscore::print_highscores(None);
+    }) as Box<dyn FnMut(_)>);
+
+    document.get_element_by_id("highscore-range").unwrap()
+        .add_event_listener_with_callback("change", callback.as_ref().unchecked_ref())?;
+
+    callback.forget();
+
+    Ok(())
+}
+
+fn register_event_listeners_puzzle_help(document: &web_sys::Document) -> Result<(), JsValue> {
+    let callback_hint = Closure::wrap(Box::new(|_e: web_sys::Event| {
+        game().show_puzzle_help(false);
+    }) as Box<dyn FnMut(_)>);
+
+    let callback_solution = Closure::wrap(Box::new(|_e: web_sys::Event| {
+        game().show_puzzle_help(true);
+    }) as Box<dyn FnMut(_)>);
+
+    document.get_element_by_id("puzzle-hint").unwrap()
+        .add_event_listener_with_callback("click", callback_hint.as_ref().unchecked_ref())?;
+    document.get_element_by_id("puzzle-solution").unwrap()
+        .add_event_listener_with_callback("click", callback_solution.as_ref().unchecked_ref())?;
+
+    callback_hint.forget();
+    callback_solution.forget();
+
+    Ok(())
+}
+
+// the board follows the size of the window, and turning a phone
+fn register_event_listener_resize() -> Result<(), JsValue> {
+    let callback = Closure::wrap(Box::new(|_e: web_sys::Event| {
+        game().relayout();
+    }) as Box<dyn FnMut(_)>);
+
+    let window = web_sys::window().unwrap();
+    window.add_event_listener_with_callback("resize", callback.as_ref().unchecked_ref())?;
+    window.add_event_listener_with_callback("orientationchange", callback.as_ref().unchecked_ref())?;
+
+    callback.forget();
+
+    Ok(())
+}
+
+// tapping or clicking a menu item chooses it
+fn register_event_listener_menu_touch(document: &web_sys::Document) -> Result<(), JsValue> {
+    let callback = Closure::wrap(Box::new(|e: web_sys::MouseEvent| {
+        if let Some(item) = game().menu_tap(e.offset_x() as f64, e.offset_y() as f64) {
+            choose(item);
+        }
+    }) as Box<dyn FnMut(_)>);
+
+    document.get_element_by_id("canvas").unwrap()
+        .add_event_listener_with_callback("click", callback.as_ref().unchecked_ref())?;
+
+    callback.forget();
+
+    Ok(())
+}
+
+// browsers don't send events for gamepad buttons, so they are polled all the time
+fn register_gamepad_polling() -> Result<(), JsValue> {
+    let mut gamepads = Gamepads::default();
+    let callback = Closure::wrap(Box::new(move || {
+        for button in gamepads.poll() {
+            if game().has_menu() {
+                match button {
+                    Button::Up => menu_input(MenuInput::Up),
+                    Button::Down => menu_input(MenuInput::Down),
+                    Button::A | Button::Start => menu_input(MenuInput::Select),
+                    Button::B => menu_input(MenuInput::Back),
+                    Button::Left | Button::Right | Button::LeftShoulder | Button::RightShoulder => (),
+                }
+            } else if !game().is_over() {
+                let input = match button {
+                    Button::Up => Some('w'),
+                    Button::Down => Some('s'),
+                    Button::Left => Some('a'),
+                    Button::Right => Some('d'),
+                    // north-west and north-east on hexagonal boards
+                    Button::LeftShoulder => Some('q'),
+                    Button::RightShoulder => Some('e'),
+                    Button::Start => Some(' '),
+                    Button::A | Button::B => None,
+                };
+                if let Some(input) = input {
+                    game().set_input(input);
+                }
+            }
+        }
+    }) as Box<dyn FnMut()>);
+
+    web_sys::window().unwrap()
+        .set_interval_with_callback_and_timeout_and_arguments_0(callback.as_ref().unchecked_ref(), GAMEPAD_POLL_INTERVAL_MS)?;
+
+    callback.forget();
+
+    Ok(())
+}
+
+fn menu_input(input: MenuInput) {
+    if let Some(item) = game().menu_input(input) {
+        choose(item);
+    }
+}
+
+fn choose(item: MenuItem) {
+    match item {
+        MenuItem::Start | MenuItem::Restart | MenuItem::Retry => create_game(),
+        MenuItem::NextLevel => {
+            game().select_next_level();
+            create_game();
+        },
+        MenuItem::Resume => game().resume(),
+        // the settings are the form above the board
+        MenuItem::Settings => {
+            let document = web_sys::window().unwrap().document().unwrap();
+            if let Some(element) = document.get_element_by_id("creation-bar") {
+                element.scroll_into_view();
+            }
+        },
+        MenuItem::Quit => game().quit(),
+    }
+}
+
+const AUDIO_CONTROLS: [&str; 4] = ["volume-master", "volume-effects", "volume-music", "mute"];
+
+// volumes are saved as soon as they are changed
+fn register_event_listeners_audio(document: &web_sys::Document) -> Result<(), JsValue> {
+    let callback = Closure::wrap(Box::new(|_e: web_sys::Event| {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let settings = audio_settings(&document);
+        audio::save_settings(&settings);
+        game().set_audio_settings(settings);
+    }) as Box<dyn FnMut(_)>);
+
+    for id in AUDIO_CONTROLS {
+        document.get_element_by_id(id).unwrap()
+            .add_event_listener_with_callback("input", callback.as_ref().unchecked_ref())?;
+    }
+
+    callback.forget();
+
+    Ok(())
+}
+
+fn audio_settings(document: &web_sys::Document) -> audio::AudioSettings {
+    let volume = |id: &str| {
+        let element = document.get_element_by_id(id).unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+        (element.value_as_number() / 100.0).clamp(0.0, 1.0) as f32
+    };
+    let mute_element = document.get_element_by_id("mute").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    audio::AudioSettings {
+        master: volume("volume-master"),
+        effects: volume("volume-effects"),
+        music: volume("volume-music"),
+        muted: mute_element.checked(),
+    }
+}
+
+// shows the saved volumes in the form
+fn init_audio_controls(document: &web_sys::Document) {
+    let settings = audio::load_settings();
+    let set_volume = |id: &str, volume: f32| {
+        let element = document.get_element_by_id(id).unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+        element.set_value_as_number((volume * 100.0).round() as f64);
+    };
+    set_volume("volume-master", settings.master);
+    set_volume("volume-effects", settings.effects);
+    set_volume("volume-music", settings.music);
+    let mute_element = document.get_element_by_id("mute").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    mute_element.set_checked(settings.muted);
+    game().set_audio_settings(settings);
+}
+
+fn register_event_listener_language(document: &web_sys::Document) -> Result<(), JsValue> {
+    let callback = Closure::wrap(Box::new(|_e: web_sys::Event| {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let language_element = document.get_element_by_id("language").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+        let language = i18n::Language::from_value(&language_element.value());
+        i18n::set_language(language);
+        i18n::save_language(language);
+        i18n::translate_page(&document);
+        profile::print_profiles();
+        achievements::print_achievements();
+        highscore::print_highscores(None);
+        campaign::print_levels(campaign::selected_level());
+        puzzle::print_puzzles();
+        game().translate_menu();
+    }) as Box<dyn FnMut(_)>);
+
+    document.get_element_by_id("language").unwrap()
+        .add_event_listener_with_callback("change", callback.as_ref().unchecked_ref())?;
+
+    callback.forget();
+
+    Ok(())
+}
+
+// shows the page in the saved language
+fn init_language(document: &web_sys::Document) {
+    let language = i18n::load_language();
+    i18n::set_language(language);
+    let language_element = document.get_element_by_id("language").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    language_element.set_value(language.value());
+    i18n::translate_page(document);
+}
+
+// columns and rows chosen in the form
+fn board_size(document: &web_sys::Document) -> (i32, i32) {
+    let columns_element = document.get_element_by_id("columns").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let rows_element = document.get_element_by_id("rows").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let columns = (columns_element.value_as_number() as i32).clamp(world::MIN_BOARD_CELLS, world::MAX_BOARD_CELLS);
+    let rows = (rows_element.value_as_number() as i32).clamp(world::MIN_BOARD_CELLS, world::MAX_BOARD_CELLS);
+    (columns, rows)
+}
+
+fn display_settings(document: &web_sys::Document) -> DisplaySettings {
+    let block_size_element = document.get_element_by_id("block-size").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let grid_element = document.get_element_by_id("grid").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let animations_element = document.get_element_by_id("animations").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let smooth_element = document.get_element_by_id("smooth").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let palette_element = document.get_element_by_id("palette").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    let food_shapes_element = document.get_element_by_id("food-shapes").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let reduced_motion_element = document.get_element_by_id("reduced-motion").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let countdown_element = document.get_element_by_id("countdown").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    DisplaySettings {
+        max_block_size: block_size_element.value_as_number() as u32,
+        draw_grid: grid_element.checked(),
+        animations: animations_element.checked(),
+        smooth: smooth_element.checked(),
+        palette: palette::Palette::from_value(&palette_element.value()),
+        food_shapes: food_shapes_element.checked(),
+        reduced_motion: reduced_motion_element.checked(),
+        countdown: (countdown_element.value_as_number() as u32).min(MAX_COUNTDOWN),
+    }
+}
+
+// reduced motion starts out as the system setting
+fn init_reduced_motion(document: &web_sys::Document) {
+    let window = web_sys::window().unwrap();
+    let prefers_reduced_motion = match window.match_media("(prefers-reduced-motion: reduce)") {
+        Ok(Some(query)) => query.matches(),
+        _ => false,
+    };
+    let element = document.get_element_by_id("reduced-motion").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    element.set_checked(prefers_reduced_motion);
+}
+
+fn canvas_context(document: &web_sys::Document) -> web_sys::CanvasRenderingContext2d {
+    let canvas = document.get_element_by_id("canvas").unwrap();
+    let canvas: web_sys::HtmlCanvasElement = canvas
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .map_err(|_| ())
+        .unwrap();
+    canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .unwrap()
+}
+
 pub fn create_game() {
     log!("creating game");
     let document = web_sys::window().unwrap().document().unwrap();
 
-    let width_element = document.get_element_by_id("width").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
-    let height_element = document.get_element_by_id("height").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
-    let block_size_element = document.get_element_by_id("block-size").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
-    let grid_element = document.get_element_by_id("grid").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
     let touch_mode_element = document.get_element_by_id("touch-mode").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let portals_element = document.get_element_by_id("portals").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let hazards_element = document.get_element_by_id("hazards").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let power_ups_element = document.get_element_by_id("power-ups").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
     let game_mode_element = document.get_element_by_id("game-mode").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
-    let name_element = document.get_element_by_id("name").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
+    let profile_element = document.get_element_by_id("profile").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    let campaign_level_element = document.get_element_by_id("campaign-level").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    let time_budget_element = document.get_element_by_id("time-budget").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    let puzzle_element = document.get_element_by_id("puzzle").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    let opponent_count_element = document.get_element_by_id("opponent-count").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    let opponent_difficulty_element = document.get_element_by_id("opponent-difficulty").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    let board_element = document.get_element_by_id("board").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    let maze_element = document.get_element_by_id("maze").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    let maze_density_element = document.get_element_by_id("maze-density").unwrap().dyn_into::<web_sys::HtmlInputElement>().unwrap();
     log!("  got parameter elements");
 
-    let width = width_element.value_as_number() as u32;
-    let height = height_element.value_as_number() as u32;
-    let block_size = block_size_element.value_as_number() as u32;
-    let draw_grid = grid_element.checked();
+    let (columns, rows) = board_size(&document);
     let touch_mode = touch_mode_element.checked();
+    let random_portals = if portals_element.checked() {RANDOM_PORTALS} else {0};
+    let hazards = if hazards_element.checked() {RANDOM_HAZARDS.to_vec()} else {Vec::new()};
     let game_mode_str = game_mode_element.value();
     let game_mode = if game_mode_str.starts_with("Fast") {
-        game::GameMode::FAST
+        world::GameMode::FAST
+    } else if game_mode_str.starts_with("Campaign") {
+        world::GameMode::CAMPAIGN
+    } else if game_mode_str.starts_with("TimeAttack") {
+        world::GameMode::TIME_ATTACK
+    } else if game_mode_str.starts_with("Survival") {
+        world::GameMode::SURVIVAL
+    } else if game_mode_str.starts_with("Versus") {
+        world::GameMode::VERSUS
+    } else if game_mode_str.starts_with("Puzzle") {
+        world::GameMode::PUZZLE
+    } else {
+        world::GameMode::LONG
+    };
+    let level = if game_mode == world::GameMode::CAMPAIGN {
+        campaign_level_element.value().parse::<usize>().ok()
+    } else {
+        None
+    };
+    let time_budget = if game_mode == world::GameMode::TIME_ATTACK {
+        time_budget_element.value().parse::<u32>().ok().map(|seconds| seconds * 1000)
     } else {
-        game::GameMode::LONG
+        None
     };
-    let name = name_element.value();
+    let maze_generator = match maze_element.value().as_str() {
+        "Backtracker" => Some(maze::MazeGenerator::Backtracker),
+        "Caves" => Some(maze::MazeGenerator::Caves),
+        "Pillars" => Some(maze::MazeGenerator::Pillars),
+        _ => None,
+    };
+    let maze = maze_generator.map(|generator| maze::MazeConfig {
+        generator,
+        density: maze_density_element.value_as_number().clamp(0.0, 100.0) as u32,
+    });
+    let puzzle = if game_mode == world::GameMode::PUZZLE {
+        puzzle_element.value().parse::<usize>().ok()
+    } else {
+        None
+    };
+    let opponents = if game_mode == world::GameMode::VERSUS {
+        let difficulty = match opponent_difficulty_element.value().as_str() {
+            "Easy" => ai::Difficulty::Easy,
+            "Hard" => ai::Difficulty::Hard,
+            _ => ai::Difficulty::Medium,
+        };
+        let count = opponent_count_element.value().parse::<usize>().unwrap_or(1).clamp(1, 3);
+        vec![difficulty; count]
+    } else {
+        Vec::new()
+    };
+    let board = if board_element.value() == "Hex" {world::BoardType::Hex} else {world::BoardType::Square};
+    let name = profile_element.value();
     log!("  got parameter values");
 
-    let canvas = document.get_element_by_id("canvas").unwrap();
-    let canvas: web_sys::HtmlCanvasElement = canvas
-        .dyn_into::<web_sys::HtmlCanvasElement>()
-        .map_err(|_| ())
-        .unwrap();
-    canvas.set_width(width);
-    canvas.set_height(height);
+    // campaign levels and puzzles bring their own board size
+    let config = world::WorldConfig {
+        columns,
+        rows,
+        touch_mode,
+        game_mode,
+        seed: utils::random_seed(),
+        scoring: scoring::load_rules(),
+        level,
+        time_budget,
+        random_portals,
+        hazards,
+        power_ups: power_ups_element.checked(),
+        maze,
+        board,
+        opponents,
+        puzzle,
+    };
 
-    let context = canvas
-        .get_context("2d")
-        .unwrap()
-        .unwrap()
-        .dyn_into::<web_sys::CanvasRenderingContext2d>()
-        .unwrap();
+    let context = canvas_context(&document);
     log!("  got canvas context");
 
+    // a loop still running from the last game carries on with the new one
+    let start_loop = !game().is_looping();
     unsafe {
-        GAME.set_state(width, height, block_size, draw_grid, touch_mode, game_mode, &name, context);
+        GAME.set_state(display_settings(&document), &name, config, context);
     }
 
-    start_world_loop();
+    if start_loop {
+        start_world_loop();
+    }
 }
 
 struct RenderLoop {
@@ -193,11 +649,8 @@ fn start_world_loop() {
 
                 let window = web_sys::window().unwrap();
                 let mut render_loop = render_loop.borrow_mut();
-                render_loop.animation_id = if let Some(ref closure) = render_loop.closure {
-                    Some(window.request_animation_frame(closure.as_ref().unchecked_ref()).expect(EXPECT_MSG))
-                } else {
-                    None
-                }
+                render_loop.animation_id = render_loop.closure.as_ref()
+                    .map(|closure| window.request_animation_frame(closure.as_ref().unchecked_ref()).expect(EXPECT_MSG));
             }))
         };
         let window = web_sys::window().unwrap();
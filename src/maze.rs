@@ -0,0 +1,174 @@
+// Procedurally generated obstacle layouts, from the seed of the game.
+// Generators only decide where walls are; World makes sure every free cell can be reached.
+
+use crate::rng::Rng;
+use crate::world::Point;
+use serde::{Deserialize, Serialize};
+
+const CAVE_SMOOTHING_STEPS: u32 = 4;
+// a cave cell becomes a wall with at least this many walls among its 8 neighbours
+const CAVE_WALL_NEIGHBOURS: u32 = 5;
+// cells in front of and behind the start of the snake that are kept free
+const START_CLEARANCE_AHEAD: i32 = 5;
+const START_CLEARANCE_BEHIND: i32 = 2;
+
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MazeGenerator {
+    // corridors carved by a recursive backtracker, walls removed again depending on density
+    Backtracker,
+    // cellular automaton smoothing random noise into caves
+    Caves,
+    // single blocks spread over the board
+    Pillars,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MazeConfig {
+    pub generator: MazeGenerator,
+    // percent, how much of the board is covered with walls
+    pub density: u32,
+}
+
+
+// walls by cell, row by row
+pub fn generate(config: &MazeConfig, columns: i32, rows: i32, start: Point, rng: &mut Rng) -> Vec<bool> {
+    let density = config.density.min(100) as i32;
+    let mut walls = match config.generator {
+        MazeGenerator::Backtracker => backtracker(columns, rows, density, rng),
+        MazeGenerator::Caves => caves(columns, rows, density, rng),
+        MazeGenerator::Pillars => pillars(columns, rows, density, rng),
+    };
+    for y in (start.y - START_CLEARANCE_BEHIND)..=(start.y + START_CLEARANCE_AHEAD) {
+        if y >= 0 && y < rows {
+            walls[(y * columns + start.x) as usize] = false;
+        }
+    }
+    walls
+}
+
+fn backtracker(columns: i32, rows: i32, density: i32, rng: &mut Rng) -> Vec<bool> {
+    let mut walls = vec![true; (columns * rows) as usize];
+    let index = |x: i32, y: i32| (y * columns + x) as usize;
+    // corridors run through odd cells
+    let mut stack = vec![Point{x: 1, y: 1}];
+    if columns > 1 && rows > 1 {
+        walls[index(1, 1)] = false;
+    }
+    while let Some(&current) = stack.last() {
+        let mut neighbours: Vec<Point> = [(0, -2), (0, 2), (-2, 0), (2, 0)].iter()
+            .map(|(dx, dy)| Point{x: current.x + dx, y: current.y + dy})
+            .filter(|p| p.x > 0 && p.y > 0 && p.x < columns - 1 && p.y < rows - 1 && walls[index(p.x, p.y)])
+            .collect();
+        if neighbours.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let next = neighbours.swap_remove(rng.range(0, neighbours.len() as i32) as usize);
+        walls[index((current.x + next.x) / 2, (current.y + next.y) / 2)] = false;
+        walls[index(next.x, next.y)] = false;
+        stack.push(next);
+    }
+    // open up the maze, the lower the density the more walls go
+    for y in 1..rows - 1 {
+        for x in 1..columns - 1 {
+            if walls[index(x, y)] && rng.range(0, 100) >= density {
+                walls[index(x, y)] = false;
+            }
+        }
+    }
+    walls
+}
+
+fn caves(columns: i32, rows: i32, density: i32, rng: &mut Rng) -> Vec<bool> {
+    let index = |x: i32, y: i32| (y * columns + x) as usize;
+    let is_border = |x: i32, y: i32| x == 0 || y == 0 || x == columns - 1 || y == rows - 1;
+    let mut walls: Vec<bool> = (0..columns * rows)
+        .map(|i| is_border(i % columns, i / columns) || rng.range(0, 100) < density)
+        .collect();
+    for _ in 0..CAVE_SMOOTHING_STEPS {
+        let previous = walls.clone();
+        for y in 0..rows {
+            for x in 0..columns {
+                if is_border(x, y) {
+                    continue;
+                }
+                let mut wall_neighbours = 0;
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        if (dx != 0 || dy != 0) && previous[index(x + dx, y + dy)] {
+                            wall_neighbours += 1;
+                        }
+                    }
+                }
+                walls[index(x, y)] = wall_neighbours >= CAVE_WALL_NEIGHBOURS;
+            }
+        }
+    }
+    walls
+}
+
+fn pillars(columns: i32, rows: i32, density: i32, rng: &mut Rng) -> Vec<bool> {
+    let mut walls = vec![false; (columns * rows) as usize];
+    // pillars never touch each other, so they cannot enclose anything
+    for y in (1..rows - 1).step_by(2) {
+        for x in (1..columns - 1).step_by(2) {
+            if rng.range(0, 100) < density {
+                walls[(y * columns + x) as usize] = true;
+            }
+        }
+    }
+    walls
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::{World, WorldConfig};
+    use std::collections::VecDeque;
+
+    const COLUMNS: i32 = 21;
+    const ROWS: i32 = 15;
+
+    // free cells the head can't get to, going around walls
+    fn unreachable_cells(world: &World) -> usize {
+        let index = |point: Point| (point.y * COLUMNS + point.x) as usize;
+        let is_free = |point: Point| point.x >= 0 && point.y >= 0 && point.x < COLUMNS && point.y < ROWS && !world.is_wall(point);
+        let mut reached = vec![false; (COLUMNS * ROWS) as usize];
+        let start = world.snake()[0];
+        reached[index(start)] = true;
+        let mut queue = VecDeque::from([start]);
+        while let Some(point) = queue.pop_front() {
+            for (dx, dy) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+                let next = Point{x: point.x + dx, y: point.y + dy};
+                if is_free(next) && !reached[index(next)] {
+                    reached[index(next)] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        (0..ROWS).flat_map(|y| (0..COLUMNS).map(move |x| Point{x, y}))
+            .filter(|point| is_free(*point) && !reached[index(*point)])
+            .count()
+    }
+
+    #[test]
+    fn every_free_cell_can_be_reached() {
+        for generator in [MazeGenerator::Backtracker, MazeGenerator::Caves, MazeGenerator::Pillars] {
+            for density in [0, 30, 60, 100] {
+                for seed in 1..20 {
+                    let world = World::new(WorldConfig {
+                        columns: COLUMNS,
+                        rows: ROWS,
+                        seed,
+                        maze: Some(MazeConfig { generator, density }),
+                        ..WorldConfig::default()
+                    });
+                    assert!(world.snake().iter().all(|point| !world.is_wall(*point)));
+                    assert_eq!(unreachable_cells(&world), 0, "density {} seed {}", density, seed);
+                }
+            }
+        }
+    }
+}
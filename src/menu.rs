@@ -0,0 +1,214 @@
+// Menus drawn on the canvas: the title screen, the pause menu and the summary after a game.
+// Keyboard, gamepad and touch are all turned into the same few inputs, so every menu works with each of them.
+// Positions are in CSS pixels.
+
+use crate::i18n;
+
+const OVERLAY_COLOUR: &str = "rgba(0, 0, 0, 0.6)";
+const TEXT_COLOUR: &str = "#FFF";
+const SELECTED_COLOUR: &str = "#FF0";
+// font size in px, following the size of the board
+const MIN_UNIT: f64 = 8.0;
+const MAX_UNIT: f64 = 28.0;
+
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum MenuItem {
+    Start,
+    Resume,
+    Restart,
+    Retry,
+    NextLevel,
+    Settings,
+    Quit,
+}
+
+impl MenuItem {
+    // looked up when drawn, so items follow a change of language
+    fn label(&self) -> String {
+        let key = match self {
+            MenuItem::Start => "menu.start",
+            MenuItem::Resume => "menu.resume",
+            MenuItem::Restart => "menu.restart",
+            MenuItem::Retry => "menu.retry",
+            MenuItem::NextLevel => "menu.next-level",
+            MenuItem::Settings => "menu.settings",
+            MenuItem::Quit => "menu.quit",
+        };
+        i18n::text(key)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Select,
+    Back,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Screen {
+    Title,
+    Pause,
+    GameOver,
+}
+
+
+pub struct Menu {
+    screen: Screen,
+    heading: String,
+    colour: &'static str,
+    // text between the heading and the items
+    lines: Vec<String>,
+    items: Vec<MenuItem>,
+    selected: usize,
+}
+
+// font size and vertical positions of a menu on a board of the given size
+struct Metrics {
+    unit: f64,
+    heading_y: f64,
+    first_line_y: f64,
+    first_item_y: f64,
+}
+
+impl Menu {
+    pub fn title() -> Self {
+        Self {
+            screen: Screen::Title,
+            heading: "SNAKE".to_string(),
+            colour: "#0A0",
+            lines: vec![i18n::text("menu.title-help")],
+            items: vec![MenuItem::Start, MenuItem::Settings],
+            selected: 0,
+        }
+    }
+
+    pub fn pause() -> Self {
+        Self {
+            screen: Screen::Pause,
+            heading: i18n::text("menu.pause"),
+            colour: "#00F",
+            lines: Vec::new(),
+            items: vec![MenuItem::Resume, MenuItem::Restart, MenuItem::Settings, MenuItem::Quit],
+            selected: 0,
+        }
+    }
+
+    pub fn game_over(heading: &str, colour: &'static str, lines: Vec<String>) -> Self {
+        Self {
+            screen: Screen::GameOver,
+            heading: heading.to_string(),
+            colour,
+            lines,
+            items: vec![MenuItem::Retry, MenuItem::Settings, MenuItem::Quit],
+            selected: 0,
+        }
+    }
+
+    // summary of a completed campaign level or puzzle, `next` when there is one after it
+    pub fn level_complete(heading: &str, lines: Vec<String>, next: bool) -> Self {
+        let mut items = vec![MenuItem::Retry, MenuItem::Settings, MenuItem::Quit];
+        if next {
+            items.insert(0, MenuItem::NextLevel);
+        }
+        Self {
+            screen: Screen::GameOver,
+            heading: heading.to_string(),
+            colour: "#0A0",
+            lines,
+            items,
+            selected: 0,
+        }
+    }
+
+    pub fn screen(&self) -> Screen {
+        self.screen
+    }
+
+    // heading and lines as one sentence each, for screen readers
+    pub fn text(&self) -> String {
+        let mut sentences = vec![self.heading.clone()];
+        sentences.extend(self.lines.iter().filter(|line| !line.is_empty()).cloned());
+        sentences.join(". ")
+    }
+
+    // the item chosen, if any
+    pub fn input(&mut self, input: MenuInput) -> Option<MenuItem> {
+        let count = self.items.len();
+        match input {
+            MenuInput::Up => self.selected = (self.selected + count - 1) % count,
+            MenuInput::Down => self.selected = (self.selected + 1) % count,
+            MenuInput::Select => return Some(self.items[self.selected]),
+            // leaving a menu: back to the game, or from the summary to the title screen
+            MenuInput::Back => return match self.screen {
+                Screen::Title => None,
+                Screen::Pause => Some(MenuItem::Resume),
+                Screen::GameOver => Some(MenuItem::Quit),
+            },
+        }
+        None
+    }
+
+    // chooses the item at a tapped or clicked position
+    pub fn tap(&mut self, x: f64, y: f64, width: f64, height: f64) -> Option<MenuItem> {
+        let metrics = self.metrics(width, height);
+        let item_height = metrics.unit * 1.5;
+        let top = metrics.first_item_y - item_height / 2.0;
+        if x < 0.0 || x > width || y < top {
+            return None;
+        }
+        let index = ((y - top) / item_height) as usize;
+        if index >= self.items.len() {
+            return None;
+        }
+        self.selected = index;
+        Some(self.items[index])
+    }
+
+    fn metrics(&self, width: f64, height: f64) -> Metrics {
+        let unit = (height / 16.0).min(width / 10.0).clamp(MIN_UNIT, MAX_UNIT);
+        // heading, lines and items, centred on the board
+        let total = unit * (2.0 + self.lines.len() as f64 + 0.5 + self.items.len() as f64 * 1.5);
+        let heading_y = (height - total) / 2.0 + unit;
+        let first_line_y = heading_y + unit * 1.5;
+        let first_item_y = first_line_y + unit * (self.lines.len() as f64 + 0.5);
+        Metrics { unit, heading_y, first_line_y, first_item_y }
+    }
+
+    pub fn draw(&self, context: &web_sys::CanvasRenderingContext2d, width: f64, height: f64) {
+        let metrics = self.metrics(width, height);
+        let unit = metrics.unit;
+        let x = width / 2.0;
+        context.set_fill_style_str(OVERLAY_COLOUR);
+        context.fill_rect(0.0, 0.0, width, height);
+        context.set_text_align("center");
+        context.set_text_baseline("middle");
+
+        context.set_font(&format!("bold {}px serif", (unit * 1.4).round()));
+        context.set_fill_style_str(self.colour);
+        crate::utils::handle_js_error(context.fill_text(&self.heading, x, metrics.heading_y));
+
+        context.set_font(&format!("{}px sans-serif", (unit * 0.7).round()));
+        context.set_fill_style_str(TEXT_COLOUR);
+        for (i, line) in self.lines.iter().enumerate() {
+            crate::utils::handle_js_error(context.fill_text(line, x, metrics.first_line_y + i as f64 * unit));
+        }
+
+        context.set_font(&format!("bold {}px sans-serif", (unit * 0.9).round()));
+        for (i, item) in self.items.iter().enumerate() {
+            let y = metrics.first_item_y + i as f64 * unit * 1.5;
+            if i == self.selected {
+                let box_width = (unit * 7.0).min(width);
+                context.set_fill_style_str(SELECTED_COLOUR);
+                context.fill_rect(x - box_width / 2.0, y - unit * 0.6, box_width, unit * 1.2);
+                context.set_fill_style_str("#000");
+            } else {
+                context.set_fill_style_str(TEXT_COLOUR);
+            }
+            crate::utils::handle_js_error(context.fill_text(&item.label(), x, y));
+        }
+        context.set_text_baseline("alphabetic");
+    }
+}
@@ -0,0 +1,93 @@
+// Colours of food, snake and opponents. Besides the original colours there is a palette that stays
+// apart with colour blindness (Okabe-Ito) and one with strong contrast to the white board.
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Palette {
+    Standard,
+    ColourBlind,
+    HighContrast,
+}
+
+// the snake takes on the colour of the food it eats
+const STANDARD_FOOD: [&str; 21] = [
+    "#050",
+    "#0A0",
+    "#0F0",
+    "#00F",
+    "#00A",
+    "#005",
+    "#055",
+    "#0AA",
+    "#0FF",
+    "#F0F",
+    "#A0A",
+    "#505",
+    "#500",
+    "#A00",
+    "#F00",
+    "#FA0",
+    "#A50",
+    "#A30",
+    "#550",
+    "#AA0",
+    "#FF0",
+];
+const COLOUR_BLIND_FOOD: [&str; 7] = [
+    "#E69F00",
+    "#56B4E9",
+    "#009E73",
+    "#F0E442",
+    "#0072B2",
+    "#D55E00",
+    "#CC79A7",
+];
+const HIGH_CONTRAST_FOOD: [&str; 6] = [
+    "#C00",
+    "#00C",
+    "#070",
+    "#909",
+    "#A50",
+    "#066",
+];
+
+const STANDARD_OPPONENTS: [&str; 3] = [
+    "#C00",
+    "#06C",
+    "#090",
+];
+const COLOUR_BLIND_OPPONENTS: [&str; 3] = [
+    "#D55E00",
+    "#0072B2",
+    "#CC79A7",
+];
+const HIGH_CONTRAST_OPPONENTS: [&str; 3] = [
+    "#F00",
+    "#00F",
+    "#0A0",
+];
+
+impl Palette {
+    pub fn from_value(value: &str) -> Self {
+        match value {
+            "ColourBlind" => Palette::ColourBlind,
+            "HighContrast" => Palette::HighContrast,
+            _ => Palette::Standard,
+        }
+    }
+
+    pub fn food(&self) -> &'static [&'static str] {
+        match self {
+            Palette::Standard => &STANDARD_FOOD,
+            Palette::ColourBlind => &COLOUR_BLIND_FOOD,
+            Palette::HighContrast => &HIGH_CONTRAST_FOOD,
+        }
+    }
+
+    pub fn opponents(&self) -> &'static [&'static str] {
+        match self {
+            Palette::Standard => &STANDARD_OPPONENTS,
+            Palette::ColourBlind => &COLOUR_BLIND_OPPONENTS,
+            Palette::HighContrast => &HIGH_CONTRAST_OPPONENTS,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Collectible power-ups with a limited duration.
+// Durations are counted in ticks so replays stay deterministic; stacking and expiry are defined in POWER_UPS.
+
+use serde::{Deserialize, Serialize};
+
+
+#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerUpKind {
+    // pass through the own body
+    Ghost,
+    // the next border hit wraps around instead of ending the game
+    Invincibility,
+    // ticks take longer
+    SlowMotion,
+    // food moves towards the head
+    Magnet,
+}
+
+// what happens when a power-up is collected while it is still active
+#[derive(Copy, Clone, PartialEq)]
+pub enum Stacking {
+    // the duration is added, up to max_ticks
+    Extend,
+    // the duration starts over
+    Refresh,
+}
+
+pub struct PowerUpSpec {
+    pub kind: PowerUpKind,
+    // the name is the message "power-up.<id>"
+    pub id: &'static str,
+    pub duration_ticks: u32,
+    pub max_ticks: u32,
+    pub stacking: Stacking,
+}
+
+pub const POWER_UPS: [PowerUpSpec; 4] = [
+    PowerUpSpec { kind: PowerUpKind::Ghost, id: "ghost", duration_ticks: 60, max_ticks: 150, stacking: Stacking::Extend },
+    PowerUpSpec { kind: PowerUpKind::Invincibility, id: "invincible", duration_ticks: 100, max_ticks: 100, stacking: Stacking::Refresh },
+    PowerUpSpec { kind: PowerUpKind::SlowMotion, id: "slow-motion", duration_ticks: 50, max_ticks: 50, stacking: Stacking::Refresh },
+    PowerUpSpec { kind: PowerUpKind::Magnet, id: "magnet", duration_ticks: 80, max_ticks: 200, stacking: Stacking::Extend },
+];
+
+// ticks between power-ups appearing, and how long one stays on the board
+pub const SPAWN_INTERVAL_TICKS: u32 = 120;
+pub const LIFETIME_TICKS: u32 = 80;
+// slow motion stretches the time between ticks by this percentage
+pub const SLOW_MOTION_PERCENT: i32 = 160;
+// magnet pulls the food one cell every this many ticks
+pub const MAGNET_PERIOD_TICKS: u32 = 2;
+
+pub fn spec(kind: PowerUpKind) -> &'static PowerUpSpec {
+    POWER_UPS.iter().find(|spec| spec.kind == kind).unwrap()
+}
+
+
+#[derive(Clone)]
+pub struct ActiveEffect {
+    pub kind: PowerUpKind,
+    pub ticks_left: u32,
+}
+
+#[derive(Clone, Default)]
+pub struct ActiveEffects {
+    effects: Vec<ActiveEffect>,
+}
+
+impl ActiveEffects {
+    pub const fn new() -> Self {
+        Self { effects: Vec::new() }
+    }
+
+    pub fn effects(&self) -> &[ActiveEffect] {
+        &self.effects
+    }
+
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    pub fn activate(&mut self, kind: PowerUpKind) {
+        let spec = spec(kind);
+        match self.effects.iter_mut().find(|effect| effect.kind == kind) {
+            Some(effect) => effect.ticks_left = match spec.stacking {
+                Stacking::Extend => (effect.ticks_left + spec.duration_ticks).min(spec.max_ticks),
+                Stacking::Refresh => spec.duration_ticks,
+            },
+            None => self.effects.push(ActiveEffect { kind, ticks_left: spec.duration_ticks }),
+        }
+    }
+
+    // for effects that are used up, like invincibility
+    pub fn consume(&mut self, kind: PowerUpKind) {
+        self.effects.retain(|effect| effect.kind != kind);
+    }
+
+    // counts down all effects by one tick and removes expired ones
+    pub fn tick(&mut self) {
+        for effect in &mut self.effects {
+            effect.ticks_left = effect.ticks_left.saturating_sub(1);
+        }
+        self.effects.retain(|effect| effect.ticks_left > 0);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::utils::log;
+use crate::utils::local_storage;
+use crate::utils::format_duration;
+use crate::highscore;
+use crate::i18n;
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use chrono::offset::Local;
+
+use wasm_bindgen::prelude::*;
+
+const STORAGE_KEY: &str = "profiles";
+const STORAGE_KEY_SELECTED: &str = "selected-profile";
+const DEFAULT_PROFILE_NAME: &str = "Player";
+const HISTORY_DAYS_SHOWN: usize = 7;
+
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Profile {
+    name: String,
+    games_played: u32,
+    food_eaten: u32,
+    longest_snake: u32,
+    // ms
+    play_time: u64,
+    // by game mode id
+    modes: BTreeMap<String, ModeStats>,
+    // by date, "%Y-%m-%d"
+    history: BTreeMap<String, DayStats>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ModeStats {
+    games_played: u32,
+    score_sum: u32,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct DayStats {
+    games_played: u32,
+    food_eaten: u32,
+    play_time: u64,
+}
+
+impl Profile {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    fn add_game(&mut self, score: u32, snake_len: u32, game_mode: &str, duration: u32, date: &str) {
+        self.games_played += 1;
+        self.food_eaten += score;
+        self.longest_snake = self.longest_snake.max(snake_len);
+        self.play_time += duration as u64;
+        let mode = self.modes.entry(game_mode.to_string()).or_default();
+        mode.games_played += 1;
+        mode.score_sum += score;
+        let day = self.history.entry(date.to_string()).or_default();
+        day.games_played += 1;
+        day.food_eaten += score;
+        day.play_time += duration as u64;
+    }
+
+    // converts game modes stored by their English names
+    fn migrate(&mut self) {
+        self.modes = std::mem::take(&mut self.modes).into_iter()
+            .map(|(game_mode, stats)| (highscore::legacy_game_mode_id(&game_mode), stats))
+            .collect();
+    }
+}
+
+
+fn load_profiles() -> Option<Vec<Profile>> {
+    let json = local_storage()?.get_item(STORAGE_KEY).ok()??;
+    let mut profiles: Vec<Profile> = serde_json::from_str(&json).ok()?;
+    profiles.iter_mut().for_each(Profile::migrate);
+    Some(profiles)
+}
+
+fn save_profiles(profiles: &[Profile]) {
+    if let Some(local_storage) = local_storage() {
+        let json = serde_json::to_string(profiles).unwrap();
+        if let Err(e) = local_storage.set_item(STORAGE_KEY, &json) {
+            log!("could not save profiles to local_storage: {:?}", e.as_string());
+        }
+    }
+}
+
+pub fn selected_profile() -> String {
+    local_storage()
+        .and_then(|local_storage| local_storage.get_item(STORAGE_KEY_SELECTED).ok().flatten())
+        .unwrap_or_else(|| DEFAULT_PROFILE_NAME.to_string())
+}
+
+fn set_selected_profile(name: &str) {
+    if let Some(local_storage) = local_storage() {
+        crate::utils::handle_js_error(local_storage.set_item(STORAGE_KEY_SELECTED, name));
+    }
+}
+
+
+// Creates profiles on first run, linking existing highscore entries to them by name, and selects the first.
+pub fn init() {
+    if load_profiles().is_some() {
+        return;
+    }
+    log!("creating profiles from highscores");
+    let mut profiles: Vec<Profile> = Vec::new();
+    for entry in highscore::load_entries() {
+        let index = match profiles.iter().position(|p| p.name == entry.name) {
+            Some(index) => index,
+            None => {
+                profiles.push(Profile::new(&entry.name));
+                profiles.len() - 1
+            },
+        };
+        let date = entry.local_date();
+        let snake_len = entry.score + crate::world::INITIAL_SNAKE_LEN as u32;
+        profiles[index].add_game(entry.score, snake_len, &entry.game_mode, entry.duration, &date);
+    }
+    if profiles.is_empty() {
+        profiles.push(Profile::new(DEFAULT_PROFILE_NAME));
+    }
+    save_profiles(&profiles);
+    // the selection would otherwise fall back to the default name, which may not be among them
+    set_selected_profile(&profiles[0].name);
+}
+
+pub fn add_profile(name: &str) {
+    let name = name.trim();
+    if name.is_empty() {
+        return;
+    }
+    let mut profiles = load_profiles().unwrap_or_default();
+    if !profiles.iter().any(|p| p.name == name) {
+        profiles.push(Profile::new(name));
+        save_profiles(&profiles);
+    }
+    set_selected_profile(name);
+}
+
+pub fn select_profile(name: &str) {
+    set_selected_profile(name);
+}
+
+pub fn record_game(name: &str, score: u32, snake_len: u32, game_mode: &str, duration: u32) {
+    let mut profiles = load_profiles().unwrap_or_default();
+    let index = match profiles.iter().position(|p| p.name == name) {
+        Some(index) => index,
+        None => {
+            profiles.push(Profile::new(name));
+            profiles.len() - 1
+        },
+    };
+    let date = Local::now().format("%Y-%m-%d").to_string();
+    profiles[index].add_game(score, snake_len, game_mode, duration, &date);
+    save_profiles(&profiles);
+}
+
+
+pub fn print_profiles() {
+    let profiles = load_profiles().unwrap_or_default();
+    let selected = selected_profile();
+    let document = web_sys::window().unwrap().document().unwrap();
+    let select = document.get_element_by_id("profile").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    while select.child_element_count() > 0 {
+        select.last_element_child().unwrap().remove();
+    }
+    for profile in &profiles {
+        let result = print_option(&document, &select, &profile.name, profile.name == selected);
+        if result.is_err() {
+            log!("could not create profile option: {:?}", result.err().unwrap().as_string());
+            break;
+        }
+    }
+    print_stats(&document, profiles.iter().find(|p| p.name == selected));
+}
+
+fn print_option(
+        document: &web_sys::Document,
+        select: &web_sys::HtmlSelectElement,
+        name: &str,
+        selected: bool)
+        -> Result<(), JsValue> {
+    let option = document.create_element("option")?;
+    option.set_attribute("value", name)?;
+    option.set_text_content(Some(name));
+    if selected {
+        option.set_attribute("selected", "")?;
+    }
+    select.append_child(&option)?;
+    Ok(())
+}
+
+fn print_stats(document: &web_sys::Document, profile: Option<&Profile>) {
+    let stats = document.get_element_by_id("profile-stats").unwrap();
+    stats.set_inner_html("");
+    let profile = match profile {
+        Some(profile) => profile,
+        None => return,
+    };
+    let mut rows: Vec<(String, String)> = vec![
+        (i18n::text("profile.games-played"), profile.games_played.to_string()),
+        (i18n::text("profile.food-eaten"), profile.food_eaten.to_string()),
+        (i18n::text("profile.longest-snake"), profile.longest_snake.to_string()),
+        (i18n::text("profile.play-time"), format_duration(profile.play_time.min(u32::MAX as u64) as u32)),
+    ];
+    for (game_mode, mode) in &profile.modes {
+        let average = mode.score_sum as f32 / mode.games_played.max(1) as f32;
+        rows.push((i18n::format("profile.average-score", &[&i18n::mode_name(game_mode)]), format!("{:.1}", average)));
+    }
+    for (date, day) in profile.history.iter().rev().take(HISTORY_DAYS_SHOWN) {
+        let play_time = format_duration(day.play_time.min(u32::MAX as u64) as u32);
+        rows.push((date.clone(), i18n::format("profile.day", &[&day.games_played.to_string(), &day.food_eaten.to_string(), &play_time])));
+    }
+    let result = print_rows(document, &stats, &rows);
+    if result.is_err() {
+        log!("could not create profile stats elements: {:?}", result.err().unwrap().as_string());
+    }
+}
+
+fn print_rows(document: &web_sys::Document, stats: &web_sys::Element, rows: &[(String, String)]) -> Result<(), JsValue> {
+    let table = document.create_element("table")?;
+    stats.append_child(&table)?;
+    for (label, value) in rows {
+        let tr = document.create_element("tr")?;
+        let th = document.create_element("th")?;
+        let td = document.create_element("td")?;
+        th.set_text_content(Some(label));
+        td.set_text_content(Some(value));
+        tr.append_child(&th)?;
+        tr.append_child(&td)?;
+        table.append_child(&tr)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,219 @@
+// Puzzles: fixed layouts with food to eat in a given order, within a limit of moves or turns.
+// The snake moves one cell per key press. Puzzles are loaded from puzzles.json and solved by
+// searching over World::step, the same logic the game runs.
+
+use crate::utils::log;
+use crate::world::{BoardType, Tick, World, WorldConfig, GameMode};
+use std::collections::{HashSet, VecDeque};
+use serde::Deserialize;
+
+use wasm_bindgen::prelude::*;
+
+// states the solver looks at before giving up
+const MAX_SOLVER_STATES: usize = 200_000;
+// hints are searched between frames; the puzzles of the pack are solved within a few thousand states
+pub const MAX_HINT_STATES: usize = 20_000;
+
+
+#[derive(Deserialize)]
+pub struct Puzzle {
+    pub name: String,
+    // like campaign layouts
+    pub layout: Vec<String>,
+    // eaten in this order, one at a time
+    pub food: Vec<(i32, i32)>,
+    #[serde(default)]
+    pub max_moves: Option<u32>,
+    #[serde(default)]
+    pub max_turns: Option<u32>,
+}
+
+impl Puzzle {
+    pub fn columns(&self) -> i32 {
+        self.layout.first().map_or(0, |row| row.len() as i32)
+    }
+    pub fn rows(&self) -> i32 {
+        self.layout.len() as i32
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PuzzlePack {
+    pub name: String,
+    pub puzzles: Vec<Puzzle>,
+}
+
+pub fn load_pack() -> PuzzlePack {
+    match serde_json::from_str(include_str!("../puzzles.json")) {
+        Ok(pack) => pack,
+        Err(error) => {
+            log!("could not parse puzzles.json: {}", error);
+            PuzzlePack { name: String::new(), puzzles: Vec::new() }
+        },
+    }
+}
+
+
+// Moves solving the puzzle from the current state of the world (breadth first search, so the fewest moves),
+// None if there is no solution or the search gives up after `max_states`.
+pub fn solve(world: &World, max_states: usize) -> Option<Vec<char>> {
+    let inputs = move_inputs(world.config().board);
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([(world.clone(), Vec::new())]);
+    while let Some((world, moves)) = queue.pop_front() {
+        for input in inputs {
+            let mut next = world.clone();
+            let result = next.step(*input);
+            // inputs reversing the snake are ignored, so the move is the direction it went
+            let mut next_moves = moves.clone();
+            next_moves.push(next.direction_input());
+            match result {
+                Tick::LevelComplete => return Some(next_moves),
+                Tick::Moved(_) => {
+                    let body: Vec<(i32, i32)> = next.snake().iter().map(|point| (point.x, point.y)).collect();
+                    // the number of turns only matters when it is limited
+                    let turns = next.turns_left().unwrap_or(0);
+                    if seen.insert((body, next.score(), turns)) {
+                        queue.push_back((next, next_moves));
+                    }
+                },
+                _ => (),
+            }
+        }
+        if seen.len() > max_states {
+            return None;
+        }
+    }
+    None
+}
+
+fn new_world(index: usize) -> World {
+    World::new(WorldConfig {
+        game_mode: GameMode::PUZZLE,
+        puzzle: Some(index),
+        ..WorldConfig::default()
+    })
+}
+
+pub fn is_solvable(index: usize) -> bool {
+    solve(&new_world(index), MAX_SOLVER_STATES).is_some()
+}
+
+// false for puzzles the world rejects, like those with the start on walls
+pub fn is_playable(index: usize) -> bool {
+    !new_world(index).is_over()
+}
+
+fn move_inputs(board: BoardType) -> &'static [char] {
+    match board {
+        BoardType::Square => &['w', 'a', 's', 'd'],
+        BoardType::Hex => &['q', 'w', 'e', 'a', 's', 'd'],
+    }
+}
+
+pub fn input_name(board: BoardType, input: char) -> &'static str {
+    match (board, input) {
+        (BoardType::Square, 'w') => "up",
+        (BoardType::Square, 'a') => "left",
+        (BoardType::Square, 's') => "down",
+        (BoardType::Square, 'd') => "right",
+        (_, 'q') => "Q",
+        (_, 'w') => "W",
+        (_, 'e') => "E",
+        (_, 'a') => "A",
+        (_, 's') => "S",
+        (_, 'd') => "D",
+        _ => "?",
+    }
+}
+
+
+// Fills the puzzle select, disabling puzzles that can't be played. Searching for solutions takes too
+// long to do for every puzzle on startup, the tests make sure the puzzles of puzzles.json are solvable.
+pub fn print_puzzles() {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let select = document.get_element_by_id("puzzle").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    // also called after a change of language, which keeps the chosen puzzle
+    let selected = select.value();
+    while select.child_element_count() > 0 {
+        select.last_element_child().unwrap().remove();
+    }
+    let pack = load_pack();
+    for (i, puzzle) in pack.puzzles.iter().enumerate() {
+        let playable = is_playable(i);
+        if !playable {
+            log!("puzzle {} \"{}\" of \"{}\" can't be played", i + 1, puzzle.name, pack.name);
+        }
+        let result = print_option(&document, &select, i, puzzle, playable);
+        if result.is_err() {
+            log!("could not create puzzle option: {:?}", result.err().unwrap().as_string());
+            break;
+        }
+    }
+    if !selected.is_empty() {
+        select.set_value(&selected);
+    }
+}
+
+pub fn select_puzzle(index: usize) {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let select = document.get_element_by_id("puzzle").unwrap().dyn_into::<web_sys::HtmlSelectElement>().unwrap();
+    select.set_value(&index.to_string());
+}
+
+fn print_option(
+        document: &web_sys::Document,
+        select: &web_sys::HtmlSelectElement,
+        index: usize,
+        puzzle: &Puzzle,
+        playable: bool)
+        -> Result<(), JsValue> {
+    let option = document.create_element("option")?;
+    option.set_attribute("value", &index.to_string())?;
+    if !playable {
+        option.set_attribute("disabled", "")?;
+    }
+    let suffix = if playable {String::new()} else {crate::i18n::text("puzzle.unsolvable")};
+    option.set_text_content(Some(&format!("{}: {}{}", index + 1, puzzle.name, suffix)));
+    select.append_child(&option)?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solutions_solve_the_puzzle() {
+        let mut world = new_world(0);
+        let moves = solve(&world, MAX_HINT_STATES).unwrap();
+        let last = moves.len() - 1;
+        for (i, input) in moves.into_iter().enumerate() {
+            let result = world.step(input);
+            if i < last {
+                assert!(matches!(result, Tick::Moved(_)));
+            } else {
+                assert!(matches!(result, Tick::LevelComplete));
+            }
+        }
+    }
+
+    #[test]
+    fn no_solution_after_game_over() {
+        let mut world = new_world(0);
+        while !world.is_over() {
+            world.step('a');
+        }
+        assert!(solve(&world, MAX_SOLVER_STATES).is_none());
+    }
+
+    #[test]
+    fn puzzles_are_solvable() {
+        for (i, puzzle) in load_pack().puzzles.iter().enumerate() {
+            assert!(is_playable(i), "puzzle {} can't be played", puzzle.name);
+            assert!(is_solvable(i), "puzzle {} isn't solvable", puzzle.name);
+            assert!(solve(&new_world(i), MAX_HINT_STATES).is_some(), "puzzle {} is too hard for hints", puzzle.name);
+        }
+    }
+}
@@ -0,0 +1,270 @@
+// Replays of finished games and their verification.
+// A replay is the world config (including the seed) plus the timing of every tick and the input,
+// which is only stored when it changes.
+// Verification re-runs the simulation headlessly, so it can run in the browser
+// as well as on a server accepting remote highscore submissions.
+
+use crate::campaign;
+use crate::puzzle;
+use crate::scoring::ScoringRules;
+use crate::world::{GameMode, Tick, World, WorldConfig, DEFAULT_INPUT, MAX_BOARD_CELLS, MIN_BOARD_CELLS};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// more than a day of ticks at the slowest speed
+const MAX_TICKS: usize = 1_000_000;
+// random portals, hazards and opponents, far more than the game places
+const MAX_PLACED: usize = 32;
+
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replay {
+    pub config: WorldConfig,
+    // ms since the tick before, for the first tick since game start
+    #[serde(default)]
+    pub gaps: Vec<u32>,
+    #[serde(default)]
+    pub inputs: Vec<InputChange>,
+    // replays stored before inputs were only stored on changes, a tick each
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ticks: Vec<ReplayTick>,
+}
+
+// ms since game start, input, key pressed
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayTick(pub u32, pub char, pub bool);
+
+// index of the tick from which on the input and key pressed hold
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputChange(pub usize, pub char, pub bool);
+
+impl Replay {
+    pub fn new(config: WorldConfig, ticks: &[ReplayTick]) -> Self {
+        let mut gaps = Vec::with_capacity(ticks.len());
+        let mut inputs: Vec<InputChange> = Vec::new();
+        let mut timestamp_before = 0;
+        for (i, tick) in ticks.iter().enumerate() {
+            gaps.push(tick.0 - timestamp_before);
+            timestamp_before = tick.0;
+            if inputs.last().is_none_or(|input| (input.1, input.2) != (tick.1, tick.2)) {
+                inputs.push(InputChange(i, tick.1, tick.2));
+            }
+        }
+        Self {
+            config,
+            gaps,
+            inputs,
+            ticks: Vec::new(),
+        }
+    }
+
+    // every tick with its input, as recorded by the world
+    pub fn ticks(&self) -> Vec<ReplayTick> {
+        if !self.ticks.is_empty() {
+            return self.ticks.clone();
+        }
+        let mut ticks = Vec::with_capacity(self.gaps.len());
+        let mut timestamp: u32 = 0;
+        let mut inputs = self.inputs.iter().peekable();
+        let mut input = (DEFAULT_INPUT, false);
+        for (i, gap) in self.gaps.iter().enumerate() {
+            timestamp = timestamp.saturating_add(*gap);
+            if let Some(change) = inputs.next_if(|change| change.0 <= i) {
+                input = (change.1, change.2);
+            }
+            ticks.push(ReplayTick(timestamp, input.0, input.1));
+        }
+        ticks
+    }
+}
+
+
+#[derive(Debug, PartialEq)]
+pub enum VerifyError {
+    // the replay's config isn't one the game could have been played with
+    InvalidConfig(&'static str),
+    TooManyTicks(usize),
+    // a tick came faster than the speed at that time allows
+    TickTooEarly(usize),
+    TicksAfterGameOver(usize),
+    GameNotOver,
+    GameModeMismatch { claimed: String, simulated: String },
+    // the replay was played with other scoring rules than the expected ones
+    ScoringRulesMismatch,
+    ScoreMismatch { claimed: u32, simulated: u32 },
+    WeightedScoreMismatch { claimed: u32, simulated: u32 },
+    DurationMismatch { claimed: u32, simulated: u32 },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::InvalidConfig(reason) => write!(f, "invalid config: {}", reason),
+            VerifyError::TooManyTicks(ticks) => write!(f, "{} ticks are more than a game can have", ticks),
+            VerifyError::TickTooEarly(i) => write!(f, "tick {} came too early", i),
+            VerifyError::TicksAfterGameOver(i) => write!(f, "tick {} came after game over", i),
+            VerifyError::GameNotOver => write!(f, "replay ends before game over"),
+            VerifyError::GameModeMismatch { claimed, simulated } =>
+                write!(f, "game mode {} does not match simulated {}", claimed, simulated),
+            VerifyError::ScoringRulesMismatch => write!(f, "scoring rules differ from the expected ones"),
+            VerifyError::ScoreMismatch { claimed, simulated } =>
+                write!(f, "score {} does not match simulated {}", claimed, simulated),
+            VerifyError::WeightedScoreMismatch { claimed, simulated } =>
+                write!(f, "weighted score {} does not match simulated {}", claimed, simulated),
+            VerifyError::DurationMismatch { claimed, simulated } =>
+                write!(f, "duration {} does not match simulated {}", claimed, simulated),
+        }
+    }
+}
+
+
+// The replay comes from the player, so its config is checked before a world is built from it.
+fn check_config(config: &WorldConfig) -> Result<(), VerifyError> {
+    let board_cells = MIN_BOARD_CELLS..=MAX_BOARD_CELLS;
+    if !board_cells.contains(&config.columns) || !board_cells.contains(&config.rows) {
+        return Err(VerifyError::InvalidConfig("board size"));
+    }
+    if config.random_portals as usize > MAX_PLACED || config.hazards.len() > MAX_PLACED || config.opponents.len() > MAX_PLACED {
+        return Err(VerifyError::InvalidConfig("too many portals, hazards or opponents"));
+    }
+    let level_known = config.level.is_some_and(|level| level < campaign::LEVELS.len());
+    if config.game_mode == GameMode::CAMPAIGN && !level_known {
+        return Err(VerifyError::InvalidConfig("unknown campaign level"));
+    }
+    let puzzle_known = config.puzzle.is_some_and(|puzzle| puzzle < puzzle::load_pack().puzzles.len());
+    if config.game_mode == GameMode::PUZZLE && !puzzle_known {
+        return Err(VerifyError::InvalidConfig("unknown puzzle"));
+    }
+    Ok(())
+}
+
+// Re-simulates the replay and checks it ends with the claimed scores, duration and game mode id.
+// `rules` are the scoring rules the verifier accepts, the ones stored in the replay come from the player.
+pub fn verify(replay: &Replay, rules: &ScoringRules, score: u32, weighted_score: u32, duration: u32, game_mode: &str) -> Result<(), VerifyError> {
+    if replay.config.scoring != *rules {
+        return Err(VerifyError::ScoringRulesMismatch);
+    }
+    let simulated_game_mode = replay.config.mode_id();
+    if simulated_game_mode != game_mode {
+        return Err(VerifyError::GameModeMismatch {
+            claimed: game_mode.to_string(),
+            simulated: simulated_game_mode,
+        });
+    }
+
+    check_config(&replay.config)?;
+    let tick_count = replay.gaps.len().max(replay.ticks.len());
+    if tick_count > MAX_TICKS || replay.inputs.len() > tick_count {
+        return Err(VerifyError::TooManyTicks(tick_count.max(replay.inputs.len())));
+    }
+
+    let mut world = World::new(replay.config.clone());
+    for (i, tick) in replay.ticks().iter().enumerate() {
+        if world.is_over() {
+            return Err(VerifyError::TicksAfterGameOver(i));
+        }
+        world.set_input(tick.1);
+        world.set_pressed(tick.2);
+        if let Tick::Waiting = world.tick(tick.0) {
+            return Err(VerifyError::TickTooEarly(i));
+        }
+    }
+    if !world.is_over() {
+        return Err(VerifyError::GameNotOver);
+    }
+
+    if world.score() != score {
+        return Err(VerifyError::ScoreMismatch { claimed: score, simulated: world.score() });
+    }
+    if world.weighted_score() != weighted_score {
+        return Err(VerifyError::WeightedScoreMismatch { claimed: weighted_score, simulated: world.weighted_score() });
+    }
+    if world.duration() != duration {
+        return Err(VerifyError::DurationMismatch { claimed: duration, simulated: world.duration() });
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::GameMode;
+
+    // a game going round in squares, with a pause, until the snake runs into itself or the border
+    fn play() -> World {
+        let mut world = World::new(WorldConfig {
+            columns: 20,
+            rows: 20,
+            game_mode: GameMode::FAST,
+            seed: 7,
+            ..WorldConfig::default()
+        });
+        let mut timestamp = 1000;
+        for i in 0..10_000 {
+            if world.is_over() {
+                break;
+            }
+            let input = match i {
+                30 | 40 => ' ',
+                31..=39 | 200.. => 'a',
+                _ => ['d', 's', 'a', 'w'][(i / 4) % 4],
+            };
+            world.set_input(input);
+            timestamp += 300;
+            world.tick(timestamp);
+        }
+        world
+    }
+
+    fn verify_world(replay: &Replay, world: &World) -> Result<(), VerifyError> {
+        verify(replay, &ScoringRules::default(), world.score(), world.weighted_score(), world.duration(), &world.config().mode_id())
+    }
+
+    #[test]
+    fn stored_replays_verify() {
+        let world = play();
+        assert!(world.is_over());
+        let json = serde_json::to_string(&world.replay()).unwrap();
+        let replay: Replay = serde_json::from_str(&json).unwrap();
+        assert_eq!(verify_world(&replay, &world), Ok(()));
+    }
+
+    #[test]
+    fn replays_with_a_tick_each_verify() {
+        let world = play();
+        let replay = world.replay();
+        let legacy = Replay { config: replay.config.clone(), gaps: Vec::new(), inputs: Vec::new(), ticks: replay.ticks() };
+        assert_eq!(verify_world(&legacy, &world), Ok(()));
+    }
+
+    #[test]
+    fn wrong_claims_are_rejected() {
+        let world = play();
+        let replay = world.replay();
+        let rules = ScoringRules::default();
+        let mode = world.config().mode_id();
+        assert_eq!(verify(&replay, &rules, world.score() + 1, world.weighted_score(), world.duration(), &mode),
+            Err(VerifyError::ScoreMismatch { claimed: world.score() + 1, simulated: world.score() }));
+        assert_eq!(verify(&replay, &rules, world.score(), world.weighted_score() + 1, world.duration(), &mode),
+            Err(VerifyError::WeightedScoreMismatch { claimed: world.weighted_score() + 1, simulated: world.weighted_score() }));
+        assert_eq!(verify(&replay, &rules, world.score(), world.weighted_score(), world.duration() + 1, &mode),
+            Err(VerifyError::DurationMismatch { claimed: world.duration() + 1, simulated: world.duration() }));
+        assert_eq!(verify(&replay, &crate::scoring::default_rules(), world.score(), world.weighted_score(), world.duration(), &mode),
+            Err(VerifyError::ScoringRulesMismatch));
+        let mut cut = replay.ticks();
+        cut.pop();
+        let unfinished = Replay { ticks: cut, ..replay.clone() };
+        assert_eq!(verify_world(&unfinished, &world), Err(VerifyError::GameNotOver));
+    }
+
+    #[test]
+    fn invalid_replays_are_rejected_before_simulating() {
+        let world = play();
+        let replay = world.replay();
+        let huge = Replay { config: WorldConfig { columns: i32::MAX, rows: i32::MAX, ..replay.config.clone() }, ..replay.clone() };
+        assert_eq!(verify_world(&huge, &world), Err(VerifyError::InvalidConfig("board size")));
+        let endless = Replay { gaps: vec![300; MAX_TICKS + 1], ..replay.clone() };
+        assert_eq!(verify_world(&endless, &world), Err(VerifyError::TooManyTicks(MAX_TICKS + 1)));
+    }
+}
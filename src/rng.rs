@@ -0,0 +1,35 @@
+// Small deterministic random number generator (xorshift64*).
+// The game is seeded with it, so a stored replay re-simulates to the same food positions.
+
+const DEFAULT_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub const fn new(seed: u64) -> Self {
+        // xorshift gets stuck at zero
+        Self {
+            state: if seed == 0 { DEFAULT_SEED } else { seed },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// random number in [min, max)
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+        min + (self.next_u64() % (max - min) as u64) as i32
+    }
+}
@@ -0,0 +1,130 @@
+// Rules to turn eaten food into points.
+// The rules are part of the world config, replays only verify when they were played with the rules
+// the verifier expects.
+
+use crate::utils::log;
+use crate::world::GameMode;
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+const STORAGE_KEY: &str = "scoring-rules";
+const DEFAULT_RULES: &str = include_str!("../scoring.json");
+
+// weights of the game modes before scoring rules existed
+const LEGACY_WEIGHT_FAST: u32 = 10;
+const LEGACY_WEIGHT_LONG: u32 = 3;
+
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringRules {
+    pub points_per_food: u32,
+    // food eaten within this many ticks after the previous one raises the combo
+    pub combo_window_ticks: u32,
+    // each combo step adds this percentage to the food points
+    pub combo_step_percent: u32,
+    pub combo_max_steps: u32,
+    // per cell between the head and the food at the time the food was placed
+    pub distance_bonus_per_cell: u32,
+    // subtracted each time the game is paused
+    pub pause_penalty: u32,
+    // points are multiplied with the weight of the game mode
+    pub mode_weights: BTreeMap<GameMode, u32>,
+}
+
+impl ScoringRules {
+    // rules matching the scores from before scoring rules existed: one point per food, legacy mode weights
+    pub const fn default() -> Self {
+        Self {
+            points_per_food: 1,
+            combo_window_ticks: 0,
+            combo_step_percent: 0,
+            combo_max_steps: 0,
+            distance_bonus_per_cell: 0,
+            pause_penalty: 0,
+            mode_weights: BTreeMap::new(),
+        }
+    }
+
+    pub fn weight(&self, game_mode: GameMode) -> u32 {
+        match self.mode_weights.get(&game_mode) {
+            Some(weight) => *weight,
+            None => legacy_weight(game_mode.id()),
+        }
+    }
+
+    pub fn food_points(&self, combo: u32, distance: u32) -> u32 {
+        self.points_per_food * (100 + combo * self.combo_step_percent) / 100
+            + distance * self.distance_bonus_per_cell
+    }
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        Self::default()
+    }
+}
+
+
+// by game mode id
+pub fn legacy_weight(game_mode: &str) -> u32 {
+    if game_mode == GameMode::FAST.id() {LEGACY_WEIGHT_FAST} else {LEGACY_WEIGHT_LONG}
+}
+
+// Rules from scoring.json, which can be overridden with a JSON object in local storage for tuning.
+pub fn load_rules() -> ScoringRules {
+    let window = web_sys::window().unwrap();
+    let custom = match window.local_storage() {
+        Ok(Some(local_storage)) => local_storage.get_item(STORAGE_KEY).ok().flatten(),
+        _ => None,
+    };
+    if let Some(json) = custom {
+        match serde_json::from_str(&json) {
+            Ok(rules) => return rules,
+            Err(e) => {
+                log!("ignoring invalid scoring rules in local storage: {}", e);
+            },
+        }
+    }
+    default_rules()
+}
+
+// the rules of scoring.json, which scores are verified against
+pub fn default_rules() -> ScoringRules {
+    serde_json::from_str(DEFAULT_RULES).unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_rules_match_legacy_scores() {
+        let rules = ScoringRules::default();
+        assert_eq!(rules.food_points(0, 7), 1);
+        assert_eq!(rules.weight(GameMode::FAST), LEGACY_WEIGHT_FAST);
+        assert_eq!(rules.weight(GameMode::LONG), LEGACY_WEIGHT_LONG);
+    }
+
+    #[test]
+    fn food_points_add_combo_and_distance() {
+        let rules = ScoringRules {
+            points_per_food: 10,
+            combo_step_percent: 50,
+            distance_bonus_per_cell: 2,
+            ..ScoringRules::default()
+        };
+        assert_eq!(rules.food_points(0, 0), 10);
+        assert_eq!(rules.food_points(2, 0), 20);
+        assert_eq!(rules.food_points(1, 3), 21);
+    }
+
+    #[test]
+    fn scoring_json_has_a_weight_per_mode() {
+        let rules = default_rules();
+        for game_mode in [GameMode::FAST, GameMode::LONG, GameMode::CAMPAIGN, GameMode::TIME_ATTACK, GameMode::SURVIVAL, GameMode::VERSUS] {
+            assert!(rules.mode_weights.contains_key(&game_mode), "no weight for {}", game_mode.id());
+        }
+    }
+}
@@ -0,0 +1,174 @@
+// Sound synthesis in plain Rust: sound effects and the music loop are rendered into mono sample buffers,
+// which the audio module only hands to WebAudio for playback. Samples are in [-1, 1].
+
+use crate::rng::Rng;
+
+// share of full scale a single tone reaches
+const TONE_VOLUME: f32 = 0.4;
+// ms of fading in and out, so tones don't click
+const ATTACK: f32 = 5.0;
+const RELEASE: f32 = 40.0;
+// a semitone higher per speed level, up to an octave
+const MAX_PITCH_SEMITONES: i32 = 12;
+const MUSIC_BEAT: f32 = 200.0;
+// semitones above A3, one per beat; -1 is a rest
+const MUSIC_MELODY: [i32; 16] = [3, 7, 10, 7, 3, 7, 12, -1, 5, 8, 12, 8, 2, 5, 10, -1];
+const MUSIC_BASS: [i32; 4] = [-9, -9, -7, -10];
+const A3: f32 = 220.0;
+
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Triangle,
+    Sawtooth,
+    Noise,
+}
+
+// a tone gliding from one frequency to another
+#[derive(Copy, Clone)]
+pub struct Tone {
+    pub waveform: Waveform,
+    // Hz
+    pub from: f32,
+    pub to: f32,
+    // ms
+    pub duration: f32,
+    pub volume: f32,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Effect {
+    Eat,
+    Turn,
+    SpeedUp,
+    Pause,
+    Death,
+    Countdown,
+}
+
+// factor for frequencies and playback rate at a speed
+pub fn pitch(speed: i32) -> f32 {
+    2f32.powf(speed.clamp(0, MAX_PITCH_SEMITONES) as f32 / 12.0)
+}
+
+fn note(semitones: i32) -> f32 {
+    A3 * 2f32.powf(semitones as f32 / 12.0)
+}
+
+pub fn render_tone(tone: &Tone, sample_rate: f32, rng: &mut Rng) -> Vec<f32> {
+    let length = (tone.duration / 1000.0 * sample_rate) as usize;
+    let attack = ATTACK / 1000.0 * sample_rate;
+    let release = (RELEASE / 1000.0 * sample_rate).min(length as f32 / 2.0);
+    let mut phase = 0.0f32;
+    (0..length).map(|i| {
+        let progress = i as f32 / length as f32;
+        let frequency = tone.from + (tone.to - tone.from) * progress;
+        phase = (phase + frequency / sample_rate).fract();
+        let value = match tone.waveform {
+            Waveform::Sine => (phase * std::f32::consts::TAU).sin(),
+            Waveform::Square => if phase < 0.5 {1.0} else {-1.0},
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Sawtooth => 2.0 * phase - 1.0,
+            Waveform::Noise => rng.range(-1000, 1001) as f32 / 1000.0,
+        };
+        let envelope = (i as f32 / attack).min(1.0).min((length - i) as f32 / release);
+        value * envelope * tone.volume * TONE_VOLUME
+    }).collect()
+}
+
+// tones one after another
+fn render_sequence(tones: &[Tone], sample_rate: f32, rng: &mut Rng) -> Vec<f32> {
+    tones.iter().flat_map(|tone| render_tone(tone, sample_rate, rng)).collect()
+}
+
+// adds `samples` into `buffer` from `offset` on, as far as the buffer goes
+fn mix_into(buffer: &mut [f32], samples: &[f32], offset: usize) {
+    for (target, sample) in buffer.iter_mut().skip(offset).zip(samples) {
+        *target = (*target + sample).clamp(-1.0, 1.0);
+    }
+}
+
+pub fn render_effect(effect: Effect, pitch: f32, sample_rate: f32) -> Vec<f32> {
+    let tone = |waveform, from: f32, to: f32, duration, volume| Tone { waveform, from: from * pitch, to: to * pitch, duration, volume };
+    let tones = match effect {
+        Effect::Eat => vec![tone(Waveform::Square, 660.0, 990.0, 70.0, 0.6)],
+        Effect::Turn => vec![tone(Waveform::Triangle, 330.0, 300.0, 30.0, 0.4)],
+        Effect::SpeedUp => vec![
+            tone(Waveform::Square, 523.0, 523.0, 70.0, 0.5),
+            tone(Waveform::Square, 659.0, 659.0, 70.0, 0.5),
+            tone(Waveform::Square, 784.0, 784.0, 120.0, 0.5),
+        ],
+        Effect::Pause => vec![
+            tone(Waveform::Sine, 880.0, 880.0, 80.0, 0.6),
+            tone(Waveform::Sine, 660.0, 660.0, 120.0, 0.6),
+        ],
+        Effect::Countdown => vec![tone(Waveform::Sine, 440.0, 440.0, 100.0, 0.6)],
+        Effect::Death => vec![
+            tone(Waveform::Sawtooth, 440.0, 110.0, 500.0, 0.7),
+            tone(Waveform::Noise, 0.0, 0.0, 300.0, 0.5),
+        ],
+    };
+    render_sequence(&tones, sample_rate, &mut Rng::new(0))
+}
+
+// a bar of melody over a bass line, to be played in a loop; faster games play it at a higher rate
+pub fn render_music(sample_rate: f32) -> Vec<f32> {
+    let beat = (MUSIC_BEAT / 1000.0 * sample_rate) as usize;
+    let mut buffer = vec![0.0; beat * MUSIC_MELODY.len()];
+    let mut rng = Rng::new(0);
+    let bass_beats = MUSIC_MELODY.len() / MUSIC_BASS.len();
+    for (i, semitones) in MUSIC_BASS.iter().enumerate() {
+        let frequency = note(*semitones);
+        let bass = Tone { waveform: Waveform::Triangle, from: frequency, to: frequency, duration: MUSIC_BEAT * bass_beats as f32, volume: 0.6 };
+        mix_into(&mut buffer, &render_tone(&bass, sample_rate, &mut rng), i * bass_beats * beat);
+    }
+    for (i, semitones) in MUSIC_MELODY.iter().enumerate().filter(|(_, semitones)| **semitones >= 0) {
+        let frequency = note(*semitones);
+        let melody = Tone { waveform: Waveform::Square, from: frequency, to: frequency, duration: MUSIC_BEAT * 0.8, volume: 0.3 };
+        mix_into(&mut buffer, &render_tone(&melody, sample_rate, &mut rng), i * beat);
+    }
+    buffer
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 8000.0;
+
+    #[test]
+    fn tones_last_their_duration_and_stay_in_range() {
+        for waveform in [Waveform::Sine, Waveform::Square, Waveform::Triangle, Waveform::Sawtooth, Waveform::Noise] {
+            let tone = Tone { waveform, from: 440.0, to: 880.0, duration: 100.0, volume: 1.0 };
+            let samples = render_tone(&tone, SAMPLE_RATE, &mut Rng::new(1));
+            assert_eq!(samples.len(), 800);
+            assert!(samples.iter().all(|sample| sample.abs() <= TONE_VOLUME));
+            // faded in and out
+            assert_eq!(samples[0], 0.0);
+            assert!(samples[samples.len() - 1].abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn effects_and_music_stay_in_range() {
+        for effect in [Effect::Eat, Effect::Turn, Effect::SpeedUp, Effect::Pause, Effect::Death, Effect::Countdown] {
+            let samples = render_effect(effect, pitch(3), SAMPLE_RATE);
+            assert!(!samples.is_empty());
+            assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+        }
+        let music = render_music(SAMPLE_RATE);
+        assert_eq!(music.len(), 1600 * MUSIC_MELODY.len());
+        assert!(music.iter().all(|sample| sample.abs() <= 1.0));
+    }
+
+    #[test]
+    fn pitch_rises_up_to_an_octave() {
+        assert_eq!(pitch(0), 1.0);
+        assert!((pitch(12) - 2.0).abs() < 1e-6);
+        assert_eq!(pitch(20), pitch(12));
+        assert_eq!(pitch(-3), 1.0);
+    }
+}
@@ -1,6 +1,7 @@
 use wasm_bindgen::prelude::*;
 use web_sys::Document;
 use crate::utils::format_duration;
+use crate::i18n;
 
 fn document() -> Document {
     web_sys::window().unwrap().document().unwrap()
@@ -10,9 +11,10 @@ fn element(document :&Document, element_id :&str) -> web_sys::HtmlElement {
     document.get_element_by_id(element_id).unwrap().dyn_into::<web_sys::HtmlElement>().unwrap()
 }
 
-pub fn update_text_display(score :u32, speed :u32) {
+pub fn update_text_display(score :u32, points :u32, speed :u32) {
     let document = document();
     element(&document, "score").set_text_content(Some(&score.to_string()));
+    element(&document, "points").set_text_content(Some(&points.to_string()));
     element(&document, "current-speed").set_text_content(Some(&speed.to_string()));
 }
 
@@ -23,9 +25,83 @@ pub fn update_speed_display(speed :i32) {
 
 pub fn update_duration_display(duration :u32) {
     let document = document();
+    element(&document, "duration-label").set_text_content(Some(&i18n::text("hud.duration")));
     element(&document, "duration").set_text_content(Some(&format_duration(duration)));
 }
 
+pub fn update_time_left_display(time_left :u32) {
+    let document = document();
+    element(&document, "duration-label").set_text_content(Some(&i18n::text("hud.time-left")));
+    element(&document, "duration").set_text_content(Some(&format_duration(time_left)));
+}
+
+pub fn update_goal_display(goal :Option<&str>) {
+    let document = document();
+    element(&document, "goal").set_text_content(Some(goal.unwrap_or("-")));
+}
+
+// active power-ups with remaining time
+pub fn update_effects_display(effects :&[(String, u32)]) {
+    let document = document();
+    let text: Vec<String> = effects.iter()
+        .map(|(name, time_left)| format!("{} {:.1}s", name, *time_left as f32 / 1000.0))
+        .collect();
+    let text = if text.is_empty() {String::new()} else {", ".to_owned() + &text.join(", ")};
+    element(&document, "effects").set_text_content(Some(&text));
+}
+
+// Touch buttons for hexagonal boards: the diagonal ones are only shown there, and left and right
+// turn into south-west and south-east.
+pub fn update_touch_controls_display(hex :bool) {
+    let document = document();
+    let display = if hex {"block"} else {"none"};
+    for id in ["touch-up-left", "touch-up-right"] {
+        crate::utils::handle_js_error(element(&document, id).style().set_property("display", display));
+    }
+    element(&document, "touch-left").set_text_content(Some(if hex {"↙️"} else {"⬅️"}));
+    element(&document, "touch-right").set_text_content(Some(if hex {"↘️"} else {"➡️"}));
+}
+
+// hunger meter of survival games, hidden in other game modes
+pub fn update_hunger_display(hunger :Option<u32>, max :u32) {
+    let document = document();
+    let block = element(&document, "hunger-block");
+    match hunger {
+        Some(hunger) => {
+            crate::utils::handle_js_error(block.style().set_property("display", "inline"));
+            let meter = element(&document, "hunger");
+            crate::utils::handle_js_error(meter.set_attribute("max", &max.to_string()));
+            crate::utils::handle_js_error(meter.set_attribute("value", &hunger.to_string()));
+        },
+        None => crate::utils::handle_js_error(block.style().set_property("display", "none")),
+    }
+}
+
+// scores of the computer snakes in their colours, dead ones crossed out
+pub fn update_opponents_display(opponents :&[(&str, u32, bool)]) {
+    let document = document();
+    let html: Vec<String> = opponents.iter()
+        .map(|(colour, score, alive)| {
+            let style = if *alive {""} else {" text-decoration: line-through;"};
+            format!("<span style=\"color: {};{}\">&#9632; {}</span>", colour, style, score)
+        })
+        .collect();
+    let html = if html.is_empty() {String::new()} else {i18n::text("hud.opponents") + &html.join(" ")};
+    element(&document, "opponents").set_inner_html(&html);
+}
+
+// hint or solution of a puzzle, empty to clear
+pub fn update_puzzle_help_display(help :&str) {
+    let document = document();
+    element(&document, "puzzle-help").set_text_content(Some(help));
+}
+
+// read out by screen readers through the aria-live region
+pub fn announce(text :&str) {
+    let document = document();
+    element(&document, "announcer").set_text_content(Some(text));
+}
+
 pub fn set_background_colour(colour :&str) {
     let document = document();
     let element = element(&document, "body");
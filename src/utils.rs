@@ -25,8 +25,13 @@ pub fn handle_js_error(result: Result<(), JsValue>) {
     }
 }
 
-pub fn random(min :u32, max :i32) -> i32 {
-    (js_sys::Math::floor(js_sys::Math::random() * max as f64 - min as f64) as u32 + min) as i32
+pub fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window().unwrap().local_storage().unwrap()
+}
+
+pub fn random_seed() -> u64 {
+    // Math.random() has 53 bits of precision
+    (js_sys::Math::random() * (1u64 << 53) as f64) as u64
 }
 
 pub fn format_duration(duration :u32) -> String {
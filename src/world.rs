@@ -0,0 +1,1164 @@
+// Simulation of the game, independent of the browser.
+// Everything that decides the outcome of a game lives here, so a stored replay can be
+// re-simulated headlessly (see replay.rs). Drawing and DOM updates stay in game.rs.
+
+use crate::rng::Rng;
+use crate::replay::{Replay, ReplayTick};
+use crate::scoring::ScoringRules;
+use crate::campaign;
+use crate::puzzle;
+use crate::hazards::{Hazard, HazardKind};
+use crate::powerups;
+use crate::powerups::{ActiveEffects, PowerUpKind};
+use crate::maze;
+use crate::maze::MazeConfig;
+use crate::hex;
+use crate::hex::HexDirection;
+use crate::ai;
+use crate::ai::{AiSnake, Difficulty};
+use serde::{Deserialize, Serialize};
+
+
+pub const DEFAULT_INPUT: char = '1';
+pub const INPUT_DOWN: char = 's';
+pub const INITIAL_SNAKE_LEN: usize = 3;
+// columns and rows a board has at least and at most
+pub const MIN_BOARD_CELLS: i32 = 5;
+pub const MAX_BOARD_CELLS: i32 = 500;
+const FRAME_RATE_SPEED_1: i32 = 1000 / 10;
+const SPEED_INCREASE_MS_MODE_KEYBOARD: i32 = 10;
+const SPEED_INCREASE_MS_MODE_TOUCH: i32 = 5;
+const SPEED_INCREASE_AT_SCORE: u32 = 3;
+const SPEED_LONG_PRESSED: i32 = 6;
+const TOUCH_MODE_FOOD_BORDER_OFFSET: i32 = 5;
+// survival: ticks until the snake loses a tail segment, refilled by eating
+pub const HUNGER_TICKS: u32 = 40;
+// random portals keep this distance to the border and the start of the snake
+const PORTAL_MARGIN: i32 = 2;
+// random hazards keep this distance to the start of the snake
+const HAZARD_MIN_DISTANCE: i32 = 6;
+// versus: the first snake reaching this length wins
+pub const VERSUS_TARGET_LENGTH: usize = 15;
+// versus: columns between the start of the player and the opponents
+const OPPONENT_MIN_DISTANCE: i32 = 3;
+
+#[derive(Copy, Clone, PartialEq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum Direction {
+    UP,
+    DOWN,
+    LEFT,
+    RIGHT,
+}
+
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BoardType {
+    #[default]
+    Square,
+    // see hex.rs
+    Hex,
+}
+
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[allow(non_camel_case_types)]
+pub enum GameMode {
+    FAST,
+    LONG,
+    CAMPAIGN,
+    TIME_ATTACK,
+    SURVIVAL,
+    VERSUS,
+    PUZZLE,
+}
+
+impl GameMode {
+    // language-neutral, stored with highscores and profiles
+    pub fn id(&self) -> &'static str {
+        match self {
+            GameMode::FAST => "fast",
+            GameMode::LONG => "long",
+            GameMode::CAMPAIGN => "campaign",
+            GameMode::TIME_ATTACK => "time-attack",
+            GameMode::SURVIVAL => "survival",
+            GameMode::VERSUS => "versus",
+            GameMode::PUZZLE => "puzzle",
+        }
+    }
+}
+
+
+// Everything needed to set up a world. Stored with every replay.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorldConfig {
+    pub columns: i32,
+    pub rows: i32,
+    pub touch_mode: bool,
+    pub game_mode: GameMode,
+    pub seed: u64,
+    #[serde(default)]
+    pub scoring: ScoringRules,
+    // index into campaign::LEVELS, defines the board size
+    #[serde(default)]
+    pub level: Option<usize>,
+    // ms, for GameMode::TIME_ATTACK
+    #[serde(default)]
+    pub time_budget: Option<u32>,
+    // number of portal pairs placed at random, in addition to those of the level
+    #[serde(default)]
+    pub random_portals: u32,
+    // hazards placed at random, in addition to those of the level
+    #[serde(default)]
+    pub hazards: Vec<HazardKind>,
+    #[serde(default)]
+    pub power_ups: bool,
+    // walls generated from the seed, ignored for campaign levels
+    #[serde(default)]
+    pub maze: Option<MazeConfig>,
+    #[serde(default)]
+    pub board: BoardType,
+    // computer snakes for GameMode::VERSUS
+    #[serde(default)]
+    pub opponents: Vec<Difficulty>,
+    // index into the puzzle pack, defines the board size
+    #[serde(default)]
+    pub puzzle: Option<usize>,
+}
+
+impl WorldConfig {
+    pub const fn default() -> Self {
+        Self {
+            columns: 1,
+            rows: 1,
+            touch_mode: false,
+            game_mode: GameMode::FAST,
+            seed: 0,
+            scoring: ScoringRules::default(),
+            level: None,
+            time_budget: None,
+            random_portals: 0,
+            hazards: Vec::new(),
+            power_ups: false,
+            maze: None,
+            board: BoardType::Square,
+            opponents: Vec::new(),
+            puzzle: None,
+        }
+    }
+
+    // id of the game mode, time attack games are distinguished by their time budget, e.g. "time-attack-60"
+    pub fn mode_id(&self) -> String {
+        match (self.game_mode, self.time_budget) {
+            (GameMode::TIME_ATTACK, Some(budget)) => format!("{}-{}", self.game_mode.id(), budget / 1000),
+            _ => self.game_mode.id().to_string(),
+        }
+    }
+}
+
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum DeathCause {
+    Border,
+    Wall,
+    Snake,
+    Starvation,
+    Hazard,
+    // ran into an opponent
+    Opponent,
+    // an opponent reached the target length first
+    Outgrown,
+    // used up the moves or turns of a puzzle
+    OutOfMoves,
+}
+
+
+// result of World::tick()
+pub enum Tick {
+    // not enough time passed since the last tick
+    Waiting,
+    Paused,
+    Moved(TickEvents),
+    Over,
+    LevelComplete,
+}
+
+#[derive(Default)]
+pub struct TickEvents {
+    pub food_eaten: bool,
+    pub speed_up: bool,
+    // a hazard cut off the tail of the snake
+    pub tail_cut: bool,
+    pub power_up_collected: Option<PowerUpKind>,
+}
+
+
+#[derive(Clone)]
+pub struct World {
+    config: WorldConfig,
+    rng: Rng,
+    speed: i32,
+    score: u32,
+    // score according to the scoring rules, without mode weight
+    points: u32,
+    combo: u32,
+    tick_count: u32,
+    tick_last_food: Option<u32>,
+    // head position when the current food was placed
+    food_spawn_head: Point,
+    snake: Vec<Point>,
+    direction: Direction,
+    // used instead of direction on hex boards
+    hex_direction: HexDirection,
+    food: Point,
+    // by cell, row by row
+    walls: Vec<bool>,
+    // by cell, whether the head can get there from the start
+    reachable: Vec<bool>,
+    // pairs of cells, a head entering one comes out of the other
+    portals: Vec<(Point, Point)>,
+    hazards: Vec<Hazard>,
+    // power-up on the board and ticks until it disappears
+    power_up: Option<(PowerUpKind, Point, u32)>,
+    ticks_until_power_up: u32,
+    effects: ActiveEffects,
+    opponents: Vec<AiSnake>,
+    // versus: outlived the opponents or reached the target length first
+    won: bool,
+    start: Option<Point>,
+    // puzzles: food in the order it has to be eaten, and the limits
+    puzzle_food: Vec<Point>,
+    max_moves: Option<u32>,
+    max_turns: Option<u32>,
+    turns: u32,
+    food_target: Option<u32>,
+    // ms
+    time_limit: Option<u32>,
+    level_complete: bool,
+    out_of_time: bool,
+    death_cause: Option<DeathCause>,
+    // survival: ticks until the next tail segment is lost
+    hunger: Option<u32>,
+    over: bool,
+    pause: bool,
+    input: char,
+    pressed: bool,
+    timestamp_last_frame: Option<u32>,
+    timestamp_game_start: Option<u32>,
+    timestamp_pause_start: u32,
+    pause_duration_sum: u32,
+    duration: u32,
+    ticks: Vec<ReplayTick>,
+}
+
+
+impl World {
+    pub const fn default() -> Self {
+        Self {
+            config: WorldConfig::default(),
+            rng: Rng::new(0),
+            speed: 0,
+            score: 0,
+            points: 0,
+            combo: 0,
+            tick_count: 0,
+            tick_last_food: None,
+            food_spawn_head: Point{x:0,y:0},
+            snake: Vec::new(),
+            direction: Direction::DOWN,
+            hex_direction: HexDirection::South,
+            food: Point{x:0,y:0},
+            walls: Vec::new(),
+            reachable: Vec::new(),
+            portals: Vec::new(),
+            hazards: Vec::new(),
+            power_up: None,
+            ticks_until_power_up: powerups::SPAWN_INTERVAL_TICKS,
+            effects: ActiveEffects::new(),
+            opponents: Vec::new(),
+            won: false,
+            start: None,
+            puzzle_food: Vec::new(),
+            max_moves: None,
+            max_turns: None,
+            turns: 0,
+            food_target: None,
+            time_limit: None,
+            level_complete: false,
+            out_of_time: false,
+            death_cause: None,
+            hunger: None,
+            over: true,
+            pause: false,
+            input: DEFAULT_INPUT,
+            pressed: false,
+            timestamp_last_frame: None,
+            timestamp_game_start: None,
+            timestamp_pause_start: 0,
+            pause_duration_sum: 0,
+            duration: 0,
+            ticks: Vec::new(),
+        }
+    }
+
+    pub fn new(config: WorldConfig) -> Self {
+        let mut world = Self {
+            rng: Rng::new(config.seed),
+            config,
+            speed: 1,
+            over: false,
+            input: INPUT_DOWN,
+            ..Self::default()
+        };
+        world.walls = vec![false; (world.config.columns * world.config.rows).max(0) as usize];
+        if let Some(level) = world.config.level.and_then(|level| campaign::LEVELS.get(level)) {
+            world.load_level(level);
+        } else if let Some(puzzle) = world.config.puzzle.and_then(|puzzle| puzzle::load_pack().puzzles.into_iter().nth(puzzle)) {
+            world.load_puzzle(&puzzle);
+        } else if let Some(maze) = world.config.maze {
+            world.generate_maze(&maze);
+        }
+        if world.config.game_mode == GameMode::TIME_ATTACK {
+            world.time_limit = world.config.time_budget;
+        }
+        if world.config.game_mode == GameMode::SURVIVAL {
+            world.hunger = Some(HUNGER_TICKS);
+        }
+        world.snake = world.init_snake();
+        for _ in 0..world.config.random_portals {
+            world.place_random_portal();
+        }
+        for (i, kind) in world.config.hazards.clone().into_iter().enumerate() {
+            world.place_random_hazard(kind, i % 2 == 0);
+        }
+        if world.config.game_mode == GameMode::VERSUS {
+            for difficulty in world.config.opponents.clone() {
+                world.place_opponent(difficulty);
+            }
+        }
+        world.reachable = world.flood_fill(world.snake[0]);
+        world.place_food();
+        world
+    }
+
+    fn load_level(&mut self, level: &campaign::Level) {
+        self.load_layout(level.layout);
+        self.speed = level.start_speed;
+        self.food_target = Some(level.food_target);
+        self.time_limit = level.time_limit;
+    }
+
+    fn load_puzzle(&mut self, puzzle: &puzzle::Puzzle) {
+        self.load_layout(&puzzle.layout);
+        // a start with the body on walls or off the board can't be played, the game is over right away
+        if self.init_snake().iter().any(|point| self.cell_index(*point).is_none() || self.is_wall(*point)) {
+            self.over = true;
+            return;
+        }
+        self.puzzle_food = puzzle.food.iter().map(|(x, y)| Point{x: *x, y: *y}).collect();
+        self.food_target = Some(self.puzzle_food.len() as u32);
+        self.max_moves = puzzle.max_moves;
+        self.max_turns = puzzle.max_turns;
+    }
+
+    // walls, start, hazards and portals of a campaign level or puzzle, see campaign.rs
+    fn load_layout<S: AsRef<str>>(&mut self, layout: &[S]) {
+        self.config.rows = layout.len() as i32;
+        self.config.columns = layout.first().map_or(0, |row| row.as_ref().len() as i32);
+        self.walls = vec![false; (self.config.columns * self.config.rows) as usize];
+        let mut portal_ends: Vec<(char, Point)> = Vec::new();
+        for (y, row) in layout.iter().enumerate() {
+            for (x, cell) in row.as_ref().chars().enumerate() {
+                let point = Point{x: x as i32, y: y as i32};
+                match cell {
+                    '#' => self.set_wall(point),
+                    'S' => self.start = Some(point),
+                    'P' => self.hazards.push(Hazard::new(HazardKind::Patrol, point, true)),
+                    'V' => self.hazards.push(Hazard::new(HazardKind::Patrol, point, false)),
+                    'B' => self.hazards.push(Hazard::new(HazardKind::Bouncer, point, false)),
+                    'H' => self.hazards.push(Hazard::new(HazardKind::Hunter, point, false)),
+                    '1'..='9' => match portal_ends.iter().position(|(c, _)| *c == cell) {
+                        Some(i) => self.portals.push((portal_ends.remove(i).1, point)),
+                        None => portal_ends.push((cell, point)),
+                    },
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    // walls every cell the generated maze cuts off, so all free cells can be reached
+    fn generate_maze(&mut self, maze: &MazeConfig) {
+        let start = self.calc_center();
+        self.walls = maze::generate(maze, self.config.columns, self.config.rows, start, &mut self.rng);
+        let reachable = self.flood_fill(start);
+        for (wall, reachable) in self.walls.iter_mut().zip(reachable) {
+            *wall = *wall || !reachable;
+        }
+    }
+
+    // cells the head can get to from `from`, going around walls and through portals
+    fn flood_fill(&self, from: Point) -> Vec<bool> {
+        let mut reachable = vec![false; self.walls.len()];
+        let mut stack = vec![from];
+        while let Some(point) = stack.pop() {
+            let i = match self.cell_index(point) {
+                Some(i) if !reachable[i] && !self.walls[i] => i,
+                _ => continue,
+            };
+            reachable[i] = true;
+            if let Some(exit) = self.portal_exit(point) {
+                stack.push(exit);
+            }
+            stack.extend(self.neighbours(point));
+        }
+        reachable
+    }
+
+    fn neighbours(&self, point: Point) -> Vec<Point> {
+        match self.config.board {
+            BoardType::Square => [(0, -1), (0, 1), (-1, 0), (1, 0)].iter()
+                .map(|(dx, dy)| Point{x: point.x + dx, y: point.y + dy})
+                .collect(),
+            BoardType::Hex => hex::neighbours(point).to_vec(),
+        }
+    }
+
+    fn init_snake(&self) -> Vec<Point> {
+        let first_point = self.start.unwrap_or_else(|| self.calc_center());
+        (0..INITIAL_SNAKE_LEN as i32)
+            .map(|i| Point{x: first_point.x, y: first_point.y - i})
+            .collect()
+    }
+
+    pub fn config(&self) -> &WorldConfig {
+        &self.config
+    }
+    pub fn game_mode(&self) -> GameMode {
+        self.config.game_mode
+    }
+    pub fn speed(&self) -> i32 {
+        self.speed
+    }
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+    pub fn weighted_score(&self) -> u32 {
+        self.points * self.config.scoring.weight(self.config.game_mode)
+    }
+    pub fn snake(&self) -> &[Point] {
+        &self.snake
+    }
+    pub fn food(&self) -> Point {
+        self.food
+    }
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+    pub fn is_paused(&self) -> bool {
+        self.pause
+    }
+    pub fn is_level_complete(&self) -> bool {
+        self.level_complete
+    }
+    pub fn is_out_of_time(&self) -> bool {
+        self.out_of_time
+    }
+    pub fn death_cause(&self) -> Option<DeathCause> {
+        self.death_cause
+    }
+    pub fn hunger(&self) -> Option<u32> {
+        self.hunger
+    }
+    pub fn food_target(&self) -> Option<u32> {
+        self.food_target
+    }
+    pub fn time_limit(&self) -> Option<u32> {
+        self.time_limit
+    }
+    // key for the direction the snake is heading in
+    pub fn direction_input(&self) -> char {
+        match (self.config.board, self.direction, self.hex_direction) {
+            (BoardType::Square, Direction::UP, _) => 'w',
+            (BoardType::Square, Direction::DOWN, _) => 's',
+            (BoardType::Square, Direction::LEFT, _) => 'a',
+            (BoardType::Square, Direction::RIGHT, _) => 'd',
+            (BoardType::Hex, _, HexDirection::North) => 'w',
+            (BoardType::Hex, _, HexDirection::NorthEast) => 'e',
+            (BoardType::Hex, _, HexDirection::SouthEast) => 'd',
+            (BoardType::Hex, _, HexDirection::South) => 's',
+            (BoardType::Hex, _, HexDirection::SouthWest) => 'a',
+            (BoardType::Hex, _, HexDirection::NorthWest) => 'q',
+        }
+    }
+    pub fn moves_left(&self) -> Option<u32> {
+        self.max_moves.map(|max| max.saturating_sub(self.tick_count))
+    }
+    pub fn turns_left(&self) -> Option<u32> {
+        self.max_turns.map(|max| max.saturating_sub(self.turns))
+    }
+
+    fn cell_index(&self, point: Point) -> Option<usize> {
+        if point.x < 0 || point.y < 0 || point.x >= self.config.columns || point.y >= self.config.rows {
+            return None;
+        }
+        Some((point.y * self.config.columns + point.x) as usize)
+    }
+    pub fn portals(&self) -> &[(Point, Point)] {
+        &self.portals
+    }
+    pub fn hazards(&self) -> &[Hazard] {
+        &self.hazards
+    }
+    pub fn power_up(&self) -> Option<(PowerUpKind, Point)> {
+        self.power_up.map(|(kind, point, _)| (kind, point))
+    }
+    pub fn effects(&self) -> &ActiveEffects {
+        &self.effects
+    }
+    pub fn opponents(&self) -> &[AiSnake] {
+        &self.opponents
+    }
+    pub fn is_won(&self) -> bool {
+        self.won
+    }
+    fn is_opponent(&self, point: Point) -> bool {
+        self.opponents.iter().any(|opponent| opponent.body.contains(&point))
+    }
+    fn is_portal(&self, point: Point) -> bool {
+        self.portals.iter().any(|(a, b)| *a == point || *b == point)
+    }
+    fn portal_exit(&self, point: Point) -> Option<Point> {
+        self.portals.iter().find_map(|(a, b)| {
+            if *a == point {
+                Some(*b)
+            } else if *b == point {
+                Some(*a)
+            } else {
+                None
+            }
+        })
+    }
+    pub fn is_wall(&self, point: Point) -> bool {
+        self.cell_index(point).is_some_and(|i| self.walls[i])
+    }
+    // a wall in the outermost cells of the board
+    fn is_border_wall(&self, point: Point) -> bool {
+        let on_border = point.x == 0 || point.y == 0 || point.x == self.config.columns - 1 || point.y == self.config.rows - 1;
+        on_border && self.is_wall(point)
+    }
+    fn set_wall(&mut self, point: Point) {
+        if let Some(i) = self.cell_index(point) {
+            self.walls[i] = true;
+        }
+    }
+    // duration of a finished game
+    pub fn duration(&self) -> u32 {
+        self.duration
+    }
+
+    pub fn replay(&self) -> Replay {
+        Replay::new(self.config.clone(), &self.ticks)
+    }
+
+    // input waiting for the next tick, DEFAULT_INPUT for none
+    pub fn input(&self) -> char {
+        self.input
+    }
+
+    pub fn set_input(&mut self, input: char) {
+        self.input = input;
+    }
+
+    pub fn set_pressed(&mut self, pressed: bool) {
+        self.pressed = pressed;
+        if self.config.game_mode == GameMode::LONG {
+            self.speed = if pressed {SPEED_LONG_PRESSED} else {1};
+        }
+    }
+
+    pub fn tick(&mut self, timestamp: u32) -> Tick {
+        let timestamp_game_start = *self.timestamp_game_start.get_or_insert(timestamp);
+        if self.over || !self.enough_time_passed(timestamp) {
+            return Tick::Waiting;
+        }
+        // puzzles only move when a direction is pressed
+        let step_only = self.config.game_mode == GameMode::PUZZLE;
+        if step_only && self.input == DEFAULT_INPUT {
+            return Tick::Waiting;
+        }
+        self.ticks.push(ReplayTick(timestamp - timestamp_game_start, self.input, self.pressed));
+        self.process_input(timestamp);
+        if step_only {
+            self.input = DEFAULT_INPUT;
+        }
+        if self.pause {
+            return Tick::Paused;
+        }
+        let mut events = TickEvents::default();
+        let alive = self.update_world(&mut events);
+        self.end_tick(alive, events, Some(timestamp))
+    }
+
+    // ends the game without a result, when the player leaves it
+    pub fn quit(&mut self) {
+        self.over = true;
+    }
+
+    // one move without timing, for the puzzle solver; time limits and the duration are left alone
+    pub fn step(&mut self, input: char) -> Tick {
+        if self.over {
+            return Tick::Over;
+        }
+        self.input = input;
+        self.process_input(0);
+        let mut events = TickEvents::default();
+        let alive = self.update_world(&mut events);
+        self.end_tick(alive, events, None)
+    }
+
+    // `timestamp` is None for steps without timing
+    fn end_tick(&mut self, alive: bool, events: TickEvents, timestamp: Option<u32>) -> Tick {
+        let duration = timestamp.map(|timestamp| self.calc_duration(timestamp));
+        if alive && self.food_target.is_some_and(|target| self.score >= target) {
+            self.level_complete = true;
+        } else if alive && self.time_limit.zip(duration).is_some_and(|(limit, duration)| duration >= limit) {
+            self.out_of_time = true;
+        } else if alive && self.is_out_of_moves() {
+            self.death_cause = Some(DeathCause::OutOfMoves);
+        } else if alive && !self.won {
+            return Tick::Moved(events);
+        }
+        self.over = true;
+        if let Some(duration) = duration {
+            self.duration = duration;
+        }
+        if self.level_complete {Tick::LevelComplete} else {Tick::Over}
+    }
+
+    fn is_out_of_moves(&self) -> bool {
+        self.moves_left() == Some(0) || self.max_turns.is_some_and(|max| self.turns > max)
+    }
+
+    fn enough_time_passed(&mut self, timestamp: u32) -> bool {
+        let enough_time_passed = match self.timestamp_last_frame {
+            Some(last) => timestamp > last && (timestamp - last) as i32 > self.frame_time_threshold(),
+            None => true,
+        };
+        if enough_time_passed {
+            self.timestamp_last_frame = Some(timestamp);
+        }
+        enough_time_passed
+    }
+
+    pub fn frame_time_threshold(&self) -> i32 {
+        let speed_increase = if self.config.touch_mode {SPEED_INCREASE_MS_MODE_TOUCH} else {SPEED_INCREASE_MS_MODE_KEYBOARD};
+        let threshold = FRAME_RATE_SPEED_1 - self.speed * speed_increase;
+        if self.effects.is_active(PowerUpKind::SlowMotion) {
+            threshold * powerups::SLOW_MOTION_PERCENT / 100
+        } else {
+            threshold
+        }
+    }
+
+    fn process_input(&mut self, timestamp: u32) {
+        let direction_before = (self.direction, self.hex_direction);
+        self.change_direction(timestamp);
+        if (self.direction, self.hex_direction) != direction_before {
+            self.turns += 1;
+        }
+    }
+
+    fn change_direction(&mut self, timestamp: u32) {
+        if self.config.board == BoardType::Hex {
+            if let Some(direction) = HexDirection::from_input(self.input) {
+                if direction != self.hex_direction.opposite() {
+                    self.hex_direction = direction;
+                }
+                return;
+            }
+        }
+        // don't allow opposite direction
+        match self.input {
+            'a' if self.direction != Direction::RIGHT => self.direction = Direction::LEFT,
+            's' if self.direction != Direction::UP => self.direction = Direction::DOWN,
+            'd' if self.direction != Direction::LEFT => self.direction = Direction::RIGHT,
+            'w' if self.direction != Direction::DOWN => self.direction = Direction::UP,
+            ' ' => self.toggle_pause(timestamp),
+            _ => (),
+        }
+    }
+
+    fn toggle_pause(&mut self, timestamp: u32) {
+        if !self.pause {
+            self.timestamp_pause_start = timestamp;
+            self.points = self.points.saturating_sub(self.config.scoring.pause_penalty);
+            self.tick_last_food = None;
+        } else {
+            self.pause_duration_sum += timestamp - self.timestamp_pause_start;
+            self.timestamp_pause_start = 0;
+        }
+        self.pause = !self.pause;
+        self.input = DEFAULT_INPUT;
+    }
+
+    fn update_world(&mut self, events: &mut TickEvents) -> bool {
+        self.tick_count += 1;
+        self.effects.tick();
+        self.move_snake();
+        self.handle_power_up_collision(events);
+        self.pull_food();
+        self.handle_food_collision(events);
+        let opponent_hit = self.update_opponents();
+        let off_board = self.check_border_collision();
+        self.death_cause = if (off_board || self.is_border_wall(self.snake[0])) && !self.wrap_invincible() {
+            Some(if off_board {DeathCause::Border} else {DeathCause::Wall})
+        } else if self.is_wall(self.snake[0]) {
+            Some(DeathCause::Wall)
+        } else if self.check_snake_collision() && !self.effects.is_active(PowerUpKind::Ghost) {
+            Some(DeathCause::Snake)
+        } else if opponent_hit {
+            Some(DeathCause::Opponent)
+        } else if self.update_hazards(events) {
+            Some(DeathCause::Hazard)
+        } else if self.starve(events) {
+            Some(DeathCause::Starvation)
+        } else if self.opponents.iter().any(|opponent| opponent.body.len() >= VERSUS_TARGET_LENGTH) {
+            Some(DeathCause::Outgrown)
+        } else {
+            None
+        };
+        if self.death_cause.is_none() && self.config.game_mode == GameMode::VERSUS {
+            // on boards too crowded for opponents only the target length wins
+            let outlived = !self.opponents.is_empty() && self.opponents.iter().all(|opponent| !opponent.alive);
+            self.won = self.won || self.snake.len() >= VERSUS_TARGET_LENGTH || outlived;
+        }
+        self.update_power_up();
+        self.death_cause.is_none()
+    }
+
+    // invincibility: the head comes out on the other side of the board, once; border walls, like those
+    // of campaign levels and mazes, are passed through as if they were the border
+    fn wrap_invincible(&mut self) -> bool {
+        if !self.effects.is_active(PowerUpKind::Invincibility) {
+            return false;
+        }
+        let mut head = self.snake[0];
+        for _ in 0..self.config.columns + self.config.rows {
+            head = Point{x: head.x.rem_euclid(self.config.columns), y: head.y.rem_euclid(self.config.rows)};
+            if !self.is_wall(head) {
+                self.effects.consume(PowerUpKind::Invincibility);
+                self.snake[0] = head;
+                return true;
+            }
+            head = self.next_cell(head);
+        }
+        false
+    }
+
+    fn handle_power_up_collision(&mut self, events: &mut TickEvents) {
+        if let Some((kind, point, _)) = self.power_up {
+            if point == self.snake[0] {
+                self.effects.activate(kind);
+                self.power_up = None;
+                events.power_up_collected = Some(kind);
+            }
+        }
+    }
+
+    // magnet: moves the food one cell towards the head
+    fn pull_food(&mut self) {
+        if !self.effects.is_active(PowerUpKind::Magnet) || !self.tick_count.is_multiple_of(powerups::MAGNET_PERIOD_TICKS) {
+            return;
+        }
+        let head = self.snake[0];
+        let dx = (head.x - self.food.x).signum();
+        let dy = (head.y - self.food.y).signum();
+        let steps = if (head.x - self.food.x).abs() >= (head.y - self.food.y).abs() {
+            [Point{x: dx, y: 0}, Point{x: 0, y: dy}]
+        } else {
+            [Point{x: 0, y: dy}, Point{x: dx, y: 0}]
+        };
+        for step in steps {
+            if step == (Point{x: 0, y: 0}) {
+                continue;
+            }
+            let next = Point{x: self.food.x + step.x, y: self.food.y + step.y};
+            let free = next == head || (!self.snake.contains(&next) && !self.is_wall(next) && !self.is_portal(next));
+            if free {
+                self.food = next;
+                return;
+            }
+        }
+    }
+
+    // lets power-ups appear and disappear
+    fn update_power_up(&mut self) {
+        if !self.config.power_ups {
+            return;
+        }
+        match self.power_up {
+            Some((kind, point, ticks_left)) => {
+                self.power_up = if ticks_left > 1 {Some((kind, point, ticks_left - 1))} else {None};
+            },
+            None => {
+                self.ticks_until_power_up -= 1;
+                if self.ticks_until_power_up == 0 {
+                    self.ticks_until_power_up = powerups::SPAWN_INTERVAL_TICKS;
+                    self.place_power_up();
+                }
+            },
+        }
+    }
+
+    fn place_power_up(&mut self) {
+        let kind = powerups::POWER_UPS[self.rng.range(0, powerups::POWER_UPS.len() as i32) as usize].kind;
+        // give up on boards too crowded for power-ups
+        for _ in 0..1000 {
+            let point = Point {
+                x: self.rng.range(0, self.config.columns),
+                y: self.rng.range(0, self.config.rows),
+            };
+            if !self.snake.contains(&point) && !self.is_wall(point) && !self.is_portal(point) && !self.is_opponent(point) && point != self.food {
+                self.power_up = Some((kind, point, powerups::LIFETIME_TICKS));
+                return;
+            }
+        }
+    }
+
+    // moves the hazards, cuts the tail where they hit the body, returns true when one hit the head
+    fn update_hazards(&mut self, events: &mut TickEvents) -> bool {
+        if self.hazards.is_empty() {
+            return false;
+        }
+        if self.hazards.iter().any(|hazard| hazard.position == self.snake[0]) {
+            return true;
+        }
+        let mut hazards = std::mem::take(&mut self.hazards);
+        let head = self.snake[0];
+        let is_free = |point: Point| self.cell_index(point).is_some() && !self.is_wall(point) && !self.is_portal(point);
+        let neighbours = |point: Point| self.neighbours(point);
+        for hazard in &mut hazards {
+            hazard.update(self.config.board, &is_free, &neighbours, head, self.config.columns, self.config.rows);
+        }
+        self.hazards = hazards;
+        let mut hit_head = false;
+        for hazard in &self.hazards {
+            match self.snake.iter().position(|point| *point == hazard.position) {
+                Some(0) => hit_head = true,
+                Some(i) => {
+                    self.snake.truncate(i);
+                    events.tail_cut = true;
+                },
+                None => (),
+            }
+        }
+        hit_head
+    }
+
+    // moves the opponents and lets them eat, removes those that crashed, returns true when the player ran into one
+    fn update_opponents(&mut self) -> bool {
+        if self.opponents.is_empty() {
+            return false;
+        }
+        let mut opponents = std::mem::take(&mut self.opponents);
+        // by cell, like walls
+        let mut blocked = self.walls.clone();
+        let occupied = opponents.iter().flat_map(|opponent| opponent.body.iter());
+        let hazards = self.hazards.iter().map(|hazard| &hazard.position);
+        for point in self.snake.iter().chain(occupied).chain(hazards) {
+            if let Some(i) = self.cell_index(*point) {
+                blocked[i] = true;
+            }
+        }
+        let is_free = |point: Point| self.cell_index(point).is_some_and(|i| !blocked[i]);
+        let neighbours = |point: Point| self.neighbours(point);
+        let board = ai::Board {
+            columns: self.config.columns,
+            rows: self.config.rows,
+            neighbours: &neighbours,
+            is_free: &is_free,
+        };
+        let mut food_eaten = false;
+        for opponent in opponents.iter_mut().filter(|opponent| opponent.alive) {
+            let mut head = opponent.next_head(&board, self.food);
+            if let Some(exit) = self.portal_exit(head) {
+                head = exit;
+            }
+            opponent.body.insert(0, head);
+            if head == self.food && !food_eaten {
+                opponent.score += 1;
+                food_eaten = true;
+            } else {
+                opponent.body.pop();
+            }
+        }
+        // crashes are decided after everyone moved, so two heads meeting both die
+        let crashed: Vec<bool> = opponents.iter().enumerate().map(|(i, opponent)| {
+            let head = match opponent.body.first() {
+                Some(head) => *head,
+                None => return false,
+            };
+            let hits_other = opponents.iter().enumerate()
+                .any(|(j, other)| j != i && other.body.contains(&head));
+            self.cell_index(head).is_none() || self.is_wall(head) || opponent.body[1..].contains(&head)
+                || self.snake.contains(&head) || hits_other || self.hazards.iter().any(|hazard| hazard.position == head)
+        }).collect();
+        let player_head = self.snake[0];
+        let player_hit = opponents.iter().any(|opponent| opponent.body.contains(&player_head));
+        for (opponent, crashed) in opponents.iter_mut().zip(crashed) {
+            if crashed {
+                opponent.alive = false;
+                opponent.body.clear();
+            }
+        }
+        self.opponents = opponents;
+        if food_eaten {
+            self.place_food();
+        }
+        player_hit
+    }
+
+    // survival: loses a tail segment when hungry, returns true when nothing is left
+    fn starve(&mut self, events: &TickEvents) -> bool {
+        let hunger = match self.hunger {
+            Some(hunger) => hunger,
+            None => return false,
+        };
+        if events.food_eaten {
+            self.hunger = Some(HUNGER_TICKS);
+            return false;
+        }
+        if hunger > 1 {
+            self.hunger = Some(hunger - 1);
+            return false;
+        }
+        self.hunger = Some(HUNGER_TICKS);
+        if self.snake.len() == 1 {
+            return true;
+        }
+        self.snake.pop();
+        false
+    }
+
+    // the cell after `point` in the direction the snake is heading
+    fn next_cell(&self, point: Point) -> Point {
+        match (self.config.board, self.direction) {
+            (BoardType::Hex, _) => hex::neighbour(point, self.hex_direction),
+            (_, Direction::UP) => Point{x: point.x, y: point.y - 1},
+            (_, Direction::DOWN) => Point{x: point.x, y: point.y + 1},
+            (_, Direction::LEFT) => Point{x: point.x - 1, y: point.y},
+            (_, Direction::RIGHT) => Point{x: point.x + 1, y: point.y},
+        }
+    }
+
+    fn move_snake(&mut self) {
+        let mut head = self.next_cell(self.snake[0]);
+        // the body follows through the portal, as it takes the previous positions of the head
+        if let Some(exit) = self.portal_exit(head) {
+            head = exit;
+        }
+        self.snake.pop();
+        self.snake.insert(0, head);
+    }
+
+    fn handle_food_collision(&mut self, events: &mut TickEvents) {
+        let head = self.snake[0];
+        // cells next to the head that count as eating in touch mode
+        let near_head = match self.config.board {
+            BoardType::Square => vec![
+                Point{x: head.x - 1, y: head.y - 1},
+                Point{x: head.x - 1, y: head.y},
+                Point{x: head.x    , y: head.y - 1},
+                Point{x: head.x + 1, y: head.y},
+                Point{x: head.x    , y: head.y + 1},
+                Point{x: head.x + 1, y: head.y + 1},
+            ],
+            BoardType::Hex => hex::neighbours(head).to_vec(),
+        };
+        let touch_mode_collision = self.config.touch_mode && near_head.contains(&self.food);
+        if head == self.food || touch_mode_collision {
+            let last_point = self.snake[self.snake.len() - 1];
+            self.snake.push(last_point);
+            self.score += 1;
+            self.add_food_points();
+            events.food_eaten = true;
+            if self.config.game_mode != GameMode::LONG && self.score.is_multiple_of(SPEED_INCREASE_AT_SCORE) {
+                self.speed += 1;
+                events.speed_up = true;
+            }
+            self.place_food();
+        }
+    }
+
+    fn add_food_points(&mut self) {
+        let rules = &self.config.scoring;
+        self.combo = match self.tick_last_food {
+            Some(tick) if self.tick_count - tick <= rules.combo_window_ticks => (self.combo + 1).min(rules.combo_max_steps),
+            _ => 0,
+        };
+        self.tick_last_food = Some(self.tick_count);
+        let distance = match self.config.board {
+            BoardType::Square => (self.food.x - self.food_spawn_head.x).unsigned_abs()
+                + (self.food.y - self.food_spawn_head.y).unsigned_abs(),
+            BoardType::Hex => hex::distance(self.food, self.food_spawn_head),
+        };
+        self.points += rules.food_points(self.combo, distance);
+    }
+
+    fn place_food(&mut self) {
+        self.food_spawn_head = self.snake[0];
+        if !self.puzzle_food.is_empty() {
+            let next = (self.score as usize).min(self.puzzle_food.len() - 1);
+            self.food = self.puzzle_food[next];
+            return;
+        }
+        let mut food_x_min = 0;
+        let mut food_y_min = 0;
+        let mut food_x_max = self.config.columns;
+        let mut food_y_max = self.config.rows;
+
+        if self.config.touch_mode {
+            food_x_min = TOUCH_MODE_FOOD_BORDER_OFFSET;
+            food_y_min = TOUCH_MODE_FOOD_BORDER_OFFSET;
+            food_x_max -= TOUCH_MODE_FOOD_BORDER_OFFSET;
+            food_y_max -= TOUCH_MODE_FOOD_BORDER_OFFSET;
+        }
+
+        for _ in 0..1000 {
+            let point = Point {
+                x: self.rng.range(food_x_min, food_x_max),
+                y: self.rng.range(food_y_min, food_y_max),
+            };
+            if self.is_free_for_food(point) {
+                self.food = point;
+                return;
+            }
+        }
+        // small or crowded boards: any free cell, the touch mode border included
+        let free: Vec<Point> = (0..self.config.rows)
+            .flat_map(|y| (0..self.config.columns).map(move |x| Point{x, y}))
+            .filter(|point| self.is_free_for_food(*point))
+            .collect();
+        if free.is_empty() {
+            // the snake fills every cell it can get to
+            self.won = true;
+            return;
+        }
+        self.food = free[self.rng.range(0, free.len() as i32) as usize];
+    }
+
+    fn is_free_for_food(&self, point: Point) -> bool {
+        let power_up_point = self.power_up.map(|(_, point, _)| point);
+        let reachable = self.cell_index(point).is_some_and(|i| self.reachable[i]);
+        let free = !self.snake.contains(&point) && !self.is_portal(point) && !self.is_opponent(point);
+        reachable && free && power_up_point != Some(point)
+    }
+
+    fn place_random_hazard(&mut self, kind: HazardKind, horizontal: bool) {
+        let start = self.snake[0];
+        // give up on boards too crowded for hazards
+        for _ in 0..1000 {
+            let point = Point {
+                x: self.rng.range(0, self.config.columns),
+                y: self.rng.range(0, self.config.rows),
+            };
+            let free = !self.snake.contains(&point) && !self.is_wall(point) && !self.is_portal(point);
+            let distance = match self.config.board {
+                BoardType::Square => (point.x - start.x).abs() + (point.y - start.y).abs(),
+                BoardType::Hex => hex::distance(point, start) as i32,
+            };
+            let in_start_column = point.x == start.x && point.y >= start.y;
+            if free && distance >= HAZARD_MIN_DISTANCE && !in_start_column {
+                self.hazards.push(Hazard::new(kind, point, horizontal));
+                return;
+            }
+        }
+    }
+
+    // an opponent in its own column, heading down like the player
+    fn place_opponent(&mut self, difficulty: Difficulty) {
+        let start = self.snake[0];
+        let length = INITIAL_SNAKE_LEN as i32;
+        // give up on boards too crowded for opponents
+        for _ in 0..1000 {
+            let head = Point {
+                x: self.rng.range(0, self.config.columns),
+                y: self.rng.range(length - 1, self.config.rows - length),
+            };
+            // the body and the way in front of the head need to be free
+            let free = (-(length - 1)..=length).all(|dy| {
+                let point = Point{x: head.x, y: head.y + dy};
+                self.cell_index(point).is_some() && !self.is_wall(point) && !self.is_portal(point)
+                    && !self.is_opponent(point) && !self.hazards.iter().any(|hazard| hazard.position == point)
+            });
+            if free && (head.x - start.x).abs() >= OPPONENT_MIN_DISTANCE {
+                let body = (0..length).map(|i| Point{x: head.x, y: head.y - i}).collect();
+                let below = Point{x: head.x, y: head.y + 1};
+                let heading = self.neighbours(head).iter().position(|point| *point == below).unwrap_or(0);
+                self.opponents.push(AiSnake::new(difficulty, body, heading));
+                return;
+            }
+        }
+    }
+
+    fn place_random_portal(&mut self) {
+        let start = self.snake[0];
+        let mut ends: Vec<Point> = Vec::new();
+        // give up on boards too crowded for portals
+        for _ in 0..1000 {
+            let point = Point {
+                x: self.rng.range(PORTAL_MARGIN, self.config.columns - PORTAL_MARGIN),
+                y: self.rng.range(PORTAL_MARGIN, self.config.rows - PORTAL_MARGIN),
+            };
+            let free = !self.snake.contains(&point) && !self.is_wall(point) && !self.is_portal(point)
+                && !ends.contains(&point);
+            // keep the way in front of the snake free
+            let in_start_column = point.x == start.x && point.y >= start.y - PORTAL_MARGIN;
+            if free && !in_start_column {
+                ends.push(point);
+                if ends.len() == 2 {
+                    self.portals.push((ends[0], ends[1]));
+                    return;
+                }
+            }
+        }
+    }
+
+    fn check_border_collision(&self) -> bool {
+        let point = &self.snake[0];
+        if self.config.board == BoardType::Hex {
+            return self.cell_index(*point).is_none();
+        }
+        match self.direction {
+            Direction::UP => point.y < 0,
+            Direction::DOWN => point.y >= self.config.rows,
+            Direction::LEFT => point.x < 0,
+            Direction::RIGHT => point.x >= self.config.columns,
+        }
+    }
+
+    fn check_snake_collision(&self) -> bool {
+        let first_point = self.snake[0];
+        self.snake[1..].contains(&first_point)
+    }
+
+    pub fn calc_duration(&self, timestamp: u32) -> u32 {
+        match self.timestamp_game_start {
+            Some(start) => timestamp.saturating_sub(start).saturating_sub(self.pause_duration_sum),
+            None => 0,
+        }
+    }
+
+    fn calc_center(&self) -> Point {
+        Point {
+            x: self.config.columns / 2,
+            y: self.config.rows / 2,
+        }
+    }
+}